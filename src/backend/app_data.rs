@@ -0,0 +1,190 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use libflatpak::{
+    gio::Cancellable,
+    glib,
+    prelude::*,
+    traits::{InstallationExt, RefExt},
+    RefKind,
+};
+
+use crate::{
+    backend::flatpak_backend::{installations, PackageId},
+    error::{BazaarError, Result},
+};
+
+/// A directory in `~/.var/app` whose app is not installed anywhere anymore.
+#[derive(Debug, Clone)]
+pub struct OrphanedData {
+    pub app: PackageId,
+    pub path: PathBuf,
+    /// In bytes
+    pub size: u64,
+}
+
+fn data_root() -> PathBuf {
+    glib::home_dir().join(".var").join("app")
+}
+
+/// `~/.var/app/<app>`, where flatpak keeps the config, data and cache of an app.
+pub fn data_dir(app: &str) -> Result<PathBuf> {
+    data_dir_in(&data_root(), app)
+}
+
+fn data_dir_in(root: &Path, app: &str) -> Result<PathBuf> {
+    // The id ends up in a path that gets deleted recursively
+    if app.is_empty() || app.starts_with('.') || app.contains('/') {
+        return Err(BazaarError::NotFound(format!("Data of {}", app)));
+    }
+    Ok(root.join(app))
+}
+
+/// Size of everything below `path`, symlinks are not followed.
+pub fn dir_size(path: &PathBuf) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| dir_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+fn installed_apps(cancellable: &Cancellable) -> Result<HashSet<String>> {
+    let mut installed = HashSet::new();
+    for installation in installations()? {
+        for pkg in installation.list_installed_refs(Some(cancellable))? {
+            if let (RefKind::App, Some(name)) = (pkg.kind(), pkg.name()) {
+                installed.insert(name.to_string());
+            }
+        }
+    }
+    Ok(installed)
+}
+
+/// Data directories without an installed app of the same id in any installation, largest first.
+pub fn find_orphaned(cancellable: &Cancellable) -> Result<Vec<OrphanedData>> {
+    let root = data_root();
+    if !root.exists() {
+        return Ok(vec![]);
+    }
+    orphaned_in(&root, &installed_apps(cancellable)?)
+}
+
+fn orphaned_in(root: &Path, installed: &HashSet<String>) -> Result<Vec<OrphanedData>> {
+    let mut orphaned = vec![];
+    for entry in std::fs::read_dir(root)? {
+        let entry = entry?;
+        let app = entry.file_name().to_string_lossy().to_string();
+        // Hidden entries are not apps, e.g. data set aside during a restore
+        if app.starts_with('.') || installed.contains(&app) || !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let path = entry.path();
+        orphaned.push(OrphanedData {
+            size: dir_size(&path),
+            app,
+            path,
+        });
+    }
+    orphaned.sort_by(|a, b| b.size.cmp(&a.size));
+    Ok(orphaned)
+}
+
+/// Deletes the data directory of `app` unless the app is still installed in some installation.
+pub fn delete_data(app: &str, cancellable: &Cancellable) -> Result<()> {
+    let path = data_dir(app)?;
+    if installed_apps(cancellable)?.contains(app) {
+        println!("{} is still installed, keeping {}", app, path.display());
+        return Ok(());
+    }
+    if path.exists() {
+        println!("Deleting {}", path.display());
+        std::fs::remove_dir_all(&path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bazaar-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn keeps_data_dirs_below_the_root() {
+        let root = Path::new("/home/user/.var/app");
+        assert_eq!(
+            data_dir_in(root, "org.gnome.Maps").unwrap(),
+            root.join("org.gnome.Maps")
+        );
+        assert!(data_dir_in(root, "").is_err());
+        assert!(data_dir_in(root, "..").is_err());
+        assert!(data_dir_in(root, ".org.gnome.Maps.bazaar-restore").is_err());
+        assert!(data_dir_in(root, "org.gnome.Maps/../..").is_err());
+    }
+
+    #[test]
+    fn sums_the_sizes_without_following_symlinks() {
+        let dir = temp_dir("data-size");
+        std::fs::create_dir_all(dir.join("config/nested")).unwrap();
+        std::fs::write(dir.join("config/settings.ini"), [0; 100]).unwrap();
+        std::fs::write(dir.join("config/nested/state"), [0; 20]).unwrap();
+        std::fs::write(dir.join("cache"), [0; 3]).unwrap();
+        let outside = temp_dir("data-size-outside");
+        std::fs::write(outside.join("big"), [0; 4096]).unwrap();
+        std::os::unix::fs::symlink(&outside, dir.join("link")).unwrap();
+
+        let link_size = std::fs::symlink_metadata(dir.join("link")).unwrap().len();
+        assert_eq!(dir_size(&dir), 123 + link_size);
+        assert_eq!(dir_size(&dir.join("cache")), 3);
+        assert_eq!(dir_size(&dir.join("missing")), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_dir_all(&outside).unwrap();
+    }
+
+    #[test]
+    fn finds_the_data_of_uninstalled_apps() {
+        let root = temp_dir("data-orphaned");
+        for (app, size) in [
+            ("org.gnome.Maps", 10),
+            ("org.foo.Old", 5),
+            ("org.bar.Older", 50),
+        ] {
+            std::fs::create_dir_all(root.join(app)).unwrap();
+            std::fs::write(root.join(app).join("data"), vec![0; size]).unwrap();
+        }
+        std::fs::create_dir_all(root.join(".org.gnome.Maps.bazaar-restore")).unwrap();
+        std::fs::write(root.join("stray-file"), "").unwrap();
+
+        let installed = HashSet::from(["org.gnome.Maps".to_string()]);
+        let orphaned: Vec<(String, u64)> = orphaned_in(&root, &installed)
+            .unwrap()
+            .into_iter()
+            .map(|data| (data.app, data.size))
+            .collect();
+        assert_eq!(
+            orphaned,
+            [
+                ("org.bar.Older".to_string(), 50),
+                ("org.foo.Old".to_string(), 5)
+            ]
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
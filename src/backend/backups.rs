@@ -0,0 +1,300 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use libflatpak::{glib, prelude::*, Instance};
+
+use crate::{
+    backend::{app_data, flatpak_backend::PackageId},
+    error::{BazaarError, Result},
+};
+
+const EXTENSION: &str = "tar.gz";
+
+/// An archive of the `~/.var/app/<id>` directory of an app.
+#[derive(Debug, Clone)]
+pub struct Backup {
+    pub app: PackageId,
+    pub path: PathBuf,
+    /// `YYYY-MM-DD HH:MM:SS`, local time
+    pub created: String,
+    /// Of the archive, in bytes
+    pub size: u64,
+}
+
+/// `$XDG_DATA_HOME/bazaar/backups/<app>`, which holds `YYYYMMDD-HHMMSS.tar.gz` archives named
+/// after the local time they were made at.
+fn backup_dir(app: &str) -> PathBuf {
+    glib::user_data_dir()
+        .join("bazaar")
+        .join("backups")
+        .join(app)
+}
+
+/// Formats the unix time `secs` in local time.
+fn local_time(secs: u64, format: &str) -> Option<String> {
+    glib::DateTime::from_unix_local(secs as i64)
+        .ok()?
+        .format(format)
+        .ok()
+        .map(|time| time.to_string())
+}
+
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn is_running(app: &str) -> bool {
+    Instance::all()
+        .iter()
+        .any(|instance| instance.is_running() && instance.app().map_or(false, |a| a == app))
+}
+
+fn ensure_not_running(app: &str) -> Result<()> {
+    if is_running(app) {
+        return Err(BazaarError::AppRunning(app.to_string()));
+    }
+    Ok(())
+}
+
+/// Archives the data of `app` into a new compressed tarball in its backup directory.
+pub fn create_backup(app: &str) -> Result<Backup> {
+    let data = app_data::data_dir(app)?;
+    if !data.exists() {
+        return Err(BazaarError::NotFound(format!("Data of {}", app)));
+    }
+    // Files an app writes while being archived could end up half written in the backup
+    ensure_not_running(app)?;
+    let now = unix_time(SystemTime::now());
+    let stamp = local_time(now, "%Y%m%d-%H%M%S").unwrap_or(now.to_string());
+    let dir = backup_dir(app);
+    std::fs::create_dir_all(&dir)?;
+    let path = new_backup_path(&dir, &stamp);
+    let partial = path.with_extension("part");
+    println!("Backing up {} to {}", data.display(), path.display());
+    let mut archive = tar::Builder::new(GzEncoder::new(
+        File::create(&partial)?,
+        Compression::default(),
+    ));
+    // Symlinks are kept as links, an app's data may point into the host
+    archive.follow_symlinks(false);
+    archive.append_dir_all(app, &data)?;
+    archive.into_inner()?.finish()?;
+    std::fs::rename(&partial, &path)?;
+    backup_of(app, path)
+}
+
+/// `<stamp>.tar.gz` in `dir`, or `<stamp>-<n>.tar.gz` if backups were already made within the
+/// same second.
+fn new_backup_path(dir: &Path, stamp: &str) -> PathBuf {
+    let mut path = dir.join(format!("{}.{}", stamp, EXTENSION));
+    let mut n = 1;
+    while path.exists() {
+        n += 1;
+        path = dir.join(format!("{}-{}.{}", stamp, n, EXTENSION));
+    }
+    path
+}
+
+/// The local time and counter a backup's file name holds, `20240131-142501-2.tar.gz` was the
+/// second backup made at `2024-01-31 14:25:01`.
+fn parse_name(name: &str) -> Option<(String, u32)> {
+    let stem = name.strip_suffix(EXTENSION)?.strip_suffix('.')?;
+    let mut parts = stem.split('-');
+    let (date, time) = (parts.next()?, parts.next()?);
+    let n = match parts.next() {
+        Some(n) => n.parse().ok()?,
+        None => 1,
+    };
+    let digits = |s: &str, len| s.len() == len && s.bytes().all(|b| b.is_ascii_digit());
+    if !digits(date, 8) || !digits(time, 6) || parts.next().is_some() {
+        return None;
+    }
+    let created = format!(
+        "{}-{}-{} {}:{}:{}",
+        &date[..4],
+        &date[4..6],
+        &date[6..],
+        &time[..2],
+        &time[2..4],
+        &time[4..]
+    );
+    Some((created, n))
+}
+
+fn backup_of(app: &str, path: PathBuf) -> Result<Backup> {
+    let metadata = std::fs::metadata(&path)?;
+    // Copied or renamed archives fall back to when they were written
+    let created = match path
+        .file_name()
+        .and_then(|n| parse_name(&n.to_string_lossy()))
+    {
+        Some((created, _)) => created,
+        None => {
+            local_time(unix_time(metadata.modified()?), "%Y-%m-%d %H:%M:%S").unwrap_or_default()
+        }
+    };
+    Ok(Backup {
+        app: app.to_string(),
+        path,
+        created,
+        size: metadata.len(),
+    })
+}
+
+/// The backups of `app`, newest first.
+pub fn list_backups(app: &str) -> Result<Vec<Backup>> {
+    backups_in(app, &backup_dir(app))
+}
+
+fn backups_in(app: &str, dir: &Path) -> Result<Vec<Backup>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut backups = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.to_string_lossy().ends_with(EXTENSION) {
+            backups.push(backup_of(app, path)?);
+        }
+    }
+    // Not by name, `-2.tar.gz` sorts before `.tar.gz` as text
+    backups.sort_by_cached_key(|backup| {
+        let name = backup
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        let n = parse_name(&name).map_or(1, |(_, n)| n);
+        std::cmp::Reverse((backup.created.clone(), n))
+    });
+    Ok(backups)
+}
+
+/// Replaces the data of the app with the content of `backup`. The current data is kept aside
+/// until the backup is unpacked so that a failed restore leaves it untouched.
+pub fn restore_backup(backup: &Backup) -> Result<()> {
+    ensure_not_running(&backup.app)?;
+    let data = app_data::data_dir(&backup.app)?;
+    let root = data
+        .parent()
+        .ok_or(BazaarError::MissingField("data directory"))?
+        .to_path_buf();
+    std::fs::create_dir_all(&root)?;
+    let aside = root.join(format!(".{}.bazaar-restore", backup.app));
+    if aside.exists() {
+        std::fs::remove_dir_all(&aside)?;
+    }
+    if data.exists() {
+        std::fs::rename(&data, &aside)?;
+    }
+    println!("Restoring {} from {}", backup.app, backup.path.display());
+    let unpacked = File::open(&backup.path)
+        .map_err(BazaarError::from)
+        .and_then(|file| {
+            let mut archive = tar::Archive::new(GzDecoder::new(file));
+            archive.set_preserve_permissions(true);
+            // Entries are checked to stay below `root` while unpacking
+            Ok(archive.unpack(&root)?)
+        });
+    match unpacked {
+        Ok(()) => {
+            if aside.exists() {
+                std::fs::remove_dir_all(&aside)?;
+            }
+            Ok(())
+        }
+        Err(err) => {
+            eprintln!("Restoring {} failed: {}", backup.app, err);
+            if data.exists() {
+                std::fs::remove_dir_all(&data)?;
+            }
+            if aside.exists() {
+                std::fs::rename(&aside, &data)?;
+            }
+            Err(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bazaar-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn parses_the_time_in_the_name() {
+        assert_eq!(
+            parse_name("20240131-142501.tar.gz"),
+            Some(("2024-01-31 14:25:01".to_string(), 1))
+        );
+        assert_eq!(
+            parse_name("20240131-142501-3.tar.gz"),
+            Some(("2024-01-31 14:25:01".to_string(), 3))
+        );
+        assert_eq!(parse_name("20240131-142501.tar.part"), None);
+        assert_eq!(parse_name("1706707501.tar.gz"), None);
+        assert_eq!(parse_name("2024131-142501.tar.gz"), None);
+        assert_eq!(parse_name("20240131-142501-x.tar.gz"), None);
+        assert_eq!(parse_name("20240131-142501-2-2.tar.gz"), None);
+    }
+
+    #[test]
+    fn does_not_overwrite_backups_of_the_same_second() {
+        let dir = temp_dir("backup-names");
+        let first = new_backup_path(&dir, "20240131-142501");
+        assert_eq!(first, dir.join("20240131-142501.tar.gz"));
+        std::fs::write(&first, "").unwrap();
+        let second = new_backup_path(&dir, "20240131-142501");
+        assert_eq!(second, dir.join("20240131-142501-2.tar.gz"));
+        std::fs::write(&second, "").unwrap();
+        assert_eq!(
+            new_backup_path(&dir, "20240131-142501"),
+            dir.join("20240131-142501-3.tar.gz")
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn lists_the_newest_backup_first() {
+        let dir = temp_dir("backup-list");
+        for name in [
+            "20240131-142501.tar.gz",
+            "20240201-090000.tar.gz",
+            "20240131-142501-2.tar.gz",
+            "20231224-180000.tar.gz",
+            "20240301-000000.tar.part",
+        ] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        let names: Vec<String> = backups_in("org.foo.Bar", &dir)
+            .unwrap()
+            .iter()
+            .map(|b| b.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "20240201-090000.tar.gz",
+                "20240131-142501-2.tar.gz",
+                "20240131-142501.tar.gz",
+                "20231224-180000.tar.gz",
+            ]
+        );
+        assert!(backups_in("org.foo.Bar", &dir.join("missing"))
+            .unwrap()
+            .is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
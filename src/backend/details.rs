@@ -0,0 +1,320 @@
+use std::cmp::Ordering;
+
+use appstream::{
+    enums::{Category, ImageKind, ProjectUrl},
+    Component,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Screenshot {
+    pub caption: Option<String>,
+    /// The full size image
+    pub url: String,
+    pub thumbnail: Option<String>,
+    pub is_default: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Release {
+    pub version: String,
+    /// `YYYY-MM-DD`
+    pub date: Option<String>,
+    /// Markup as found in the appstream data
+    pub description: Option<String>,
+}
+
+/// The appstream data of a package beyond what [`super::flatpak_backend::Package`] carries.
+#[derive(Debug, Clone, Default)]
+pub struct PackageDetails {
+    pub developer: Option<String>,
+    pub license: Option<String>,
+    pub homepage: Option<String>,
+    pub bugtracker: Option<String>,
+    pub donation: Option<String>,
+    pub categories: Vec<String>,
+    pub keywords: Vec<String>,
+    /// OARS attributes and their intensity, e.g. `("ViolenceCartoon", "Mild")`
+    pub content_rating: Vec<(String, String)>,
+    pub screenshots: Vec<Screenshot>,
+    /// Newest first, as appstream lists them
+    pub releases: Vec<Release>,
+}
+
+fn category_name(category: &Category) -> String {
+    match category {
+        Category::Unknown(name) => name.clone(),
+        other => format!("{:?}", other),
+    }
+}
+
+impl From<&Component> for PackageDetails {
+    fn from(c: &Component) -> Self {
+        let url = |matches: fn(&ProjectUrl) -> Option<String>| c.urls.iter().find_map(matches);
+        Self {
+            developer: c
+                .developer_name
+                .as_ref()
+                .and_then(|d| d.get_default().map(String::to_owned)),
+            license: c.project_license.as_ref().map(|l| l.0.clone()),
+            homepage: url(|u| match u {
+                ProjectUrl::Homepage(url) => Some(url.to_string()),
+                _ => None,
+            }),
+            bugtracker: url(|u| match u {
+                ProjectUrl::BugTracker(url) => Some(url.to_string()),
+                _ => None,
+            }),
+            donation: url(|u| match u {
+                ProjectUrl::Donation(url) => Some(url.to_string()),
+                _ => None,
+            }),
+            categories: c.categories.iter().map(category_name).collect(),
+            keywords: c
+                .keywords
+                .as_ref()
+                .and_then(|k| k.get_default().cloned())
+                .unwrap_or_default(),
+            content_rating: c
+                .content_rating
+                .as_ref()
+                .map(|rating| {
+                    // Attributes are enum variants carrying their intensity, e.g. `ViolenceCartoon(Mild)`
+                    rating
+                        .attributes
+                        .iter()
+                        .filter_map(|attribute| {
+                            let attribute = format!("{:?}", attribute);
+                            let (id, state) = attribute.split_once('(')?;
+                            Some((id.to_string(), state.trim_end_matches(')').to_string()))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            screenshots: c
+                .screenshots
+                .iter()
+                .filter_map(|s| {
+                    let source = s.images.iter().find(|i| i.kind == ImageKind::Source)?;
+                    let thumbnail = s
+                        .images
+                        .iter()
+                        .filter(|i| i.kind == ImageKind::Thumbnail)
+                        .max_by_key(|i| i.width.unwrap_or(0));
+                    Some(Screenshot {
+                        caption: s
+                            .caption
+                            .as_ref()
+                            .and_then(|c| c.get_default().map(String::to_owned)),
+                        url: source.url.to_string(),
+                        thumbnail: thumbnail.map(|t| t.url.to_string()),
+                        is_default: s.is_default,
+                    })
+                })
+                .collect(),
+            releases: c
+                .releases
+                .iter()
+                .map(|r| Release {
+                    version: r.version.clone(),
+                    date: r.date.map(|d| d.format("%Y-%m-%d").to_string()),
+                    description: r
+                        .description
+                        .as_ref()
+                        .and_then(|d| d.get_default().map(String::to_owned)),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Turns appstream description markup (`<p>`, `<ul>`/`<ol>` with `<li>`, `<em>`, `<code>`) into
+/// plain text with one paragraph or bullet per line.
+pub fn markup_to_text(markup: &str) -> String {
+    let mut text = String::new();
+    let mut rest = markup;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        // Only the name matters, e.g. `li` of `<li xml:lang="de">`
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        match name {
+            "p" | "ul" | "ol" if !text.is_empty() && !text.ends_with('\n') => text.push('\n'),
+            "li" if !tag.starts_with('/') => text.push_str("• "),
+            "li" => text.push('\n'),
+            _ => {}
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+}
+
+/// The runs of digits and of letters in a version, `1.0-rc2` has `1`, `0`, `rc` and `2`.
+fn version_segments(version: &str) -> Vec<&str> {
+    let version = version.trim_start_matches(['v', 'V']);
+    let mut segments = vec![];
+    let mut start = None;
+    for (i, c) in version.char_indices() {
+        if let Some(s) = start {
+            let previous = version[s..i].chars().last().unwrap_or(c);
+            if !c.is_ascii_alphanumeric() || previous.is_ascii_digit() != c.is_ascii_digit() {
+                segments.push(&version[s..i]);
+                start = None;
+            }
+        }
+        if start.is_none() && c.is_ascii_alphanumeric() {
+            start = Some(i);
+        }
+    }
+    if let Some(s) = start {
+        segments.push(&version[s..]);
+    }
+    segments
+}
+
+/// Compares versions such as `1.10.2` and `1.9` segment by segment, numerically where both
+/// segments are numbers. A missing number counts as `0`, so `1.0` and `1.0.0` are equal, while
+/// letters mark a pre-release of what comes without them: `1.0-rc1` is older than `1.0`.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a, b) = (version_segments(a), version_segments(b));
+    for i in 0..a.len().max(b.len()) {
+        let ordering = match (a.get(i), b.get(i)) {
+            (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                // A number continues a release, letters start a pre-release of it
+                (Ok(_), Err(_)) => Ordering::Greater,
+                (Err(_), Ok(_)) => Ordering::Less,
+                (Err(_), Err(_)) => a.cmp(b),
+            },
+            (Some(a), None) if a.parse::<u64>().is_err() => Ordering::Less,
+            (None, Some(b)) if b.parse::<u64>().is_err() => Ordering::Greater,
+            (Some(a), None) => a.parse::<u64>().unwrap_or(0).cmp(&0),
+            (None, Some(b)) => 0.cmp(&b.parse::<u64>().unwrap_or(0)),
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// The releases newer than `installed`, newest first. Without an installed version only the
+/// latest release is returned.
+pub fn releases_since(releases: &[Release], installed: Option<&str>) -> Vec<Release> {
+    let Some(installed) = installed else {
+        return releases.iter().take(1).cloned().collect();
+    };
+    let mut newer: Vec<Release> = releases
+        .iter()
+        .filter(|r| compare_versions(&r.version, installed) == Ordering::Greater)
+        .cloned()
+        .collect();
+    newer.sort_by(|a, b| compare_versions(&b.version, &a.version));
+    newer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(version: &str) -> Release {
+        Release {
+            version: version.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn compares_versions() {
+        assert_eq!(compare_versions("1.10.2", "1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("1.9", "1.10.2"), Ordering::Less);
+        assert_eq!(compare_versions("v2.0", "2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.0", "1.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.1", "1.0"), Ordering::Greater);
+        assert_eq!(compare_versions("45.beta", "45.alpha"), Ordering::Greater);
+    }
+
+    #[test]
+    fn compares_pre_releases_below_their_release() {
+        assert_eq!(compare_versions("1.0-rc1", "1.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.0", "1.0-rc1"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0.rc1", "1.0.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.0rc2", "1.0-rc1"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0-rc10", "1.0-rc9"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0-beta", "1.0-rc1"), Ordering::Less);
+        assert_eq!(compare_versions("1.0-rc1", "0.9"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0.1", "1.0-rc1"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0.1-rc1", "1.0"), Ordering::Greater);
+    }
+
+    #[test]
+    fn releases_since_a_pre_release() {
+        let releases = vec![
+            release("1.1"),
+            release("1.0"),
+            release("1.0-rc2"),
+            release("1.0-rc1"),
+        ];
+        let versions =
+            |releases: Vec<Release>| releases.into_iter().map(|r| r.version).collect::<Vec<_>>();
+        assert_eq!(
+            versions(releases_since(&releases, Some("1.0-rc1"))),
+            ["1.1", "1.0", "1.0-rc2"]
+        );
+        assert_eq!(versions(releases_since(&releases, Some("1.0"))), ["1.1"]);
+    }
+
+    #[test]
+    fn releases_since_the_installed_version() {
+        let releases = vec![
+            release("1.2"),
+            release("1.1.0"),
+            release("1.1"),
+            release("1.0"),
+        ];
+        let versions =
+            |releases: Vec<Release>| releases.into_iter().map(|r| r.version).collect::<Vec<_>>();
+        assert_eq!(
+            versions(releases_since(&releases, Some("1.0"))),
+            ["1.2", "1.1.0", "1.1"]
+        );
+        assert_eq!(versions(releases_since(&releases, Some("1.1"))), ["1.2"]);
+        assert!(releases_since(&releases, Some("1.2.0")).is_empty());
+        assert_eq!(versions(releases_since(&releases, None)), ["1.2"]);
+    }
+
+    #[test]
+    fn turns_markup_into_text() {
+        assert_eq!(
+            markup_to_text("<p>Fixes:</p><ul><li>A &amp; B</li><li><em>C</em></li></ul>"),
+            "Fixes:\n• A & B\n• C"
+        );
+        assert_eq!(
+            markup_to_text("<p xml:lang=\"de\">Erster</p>\n  <p>Second   paragraph</p>"),
+            "Erster\nSecond paragraph"
+        );
+        assert_eq!(
+            markup_to_text("<ol>\n<li class=\"item\">One</li>\n<li>Two</li>\n</ol>"),
+            "• One\n• Two"
+        );
+        assert_eq!(markup_to_text("Plain text"), "Plain text");
+    }
+}
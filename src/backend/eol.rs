@@ -0,0 +1,229 @@
+use std::collections::{HashMap, HashSet};
+
+use iced::futures::channel::mpsc;
+use libflatpak::{
+    gio::Cancellable,
+    prelude::*,
+    traits::{InstallationExt, InstalledRefExt, RefExt, RemoteRefExt},
+    Installation, RefKind, Transaction,
+};
+
+use crate::{
+    backend::{
+        flatpak_backend::{
+            find_installed_ref, installations, watch_transaction, InstallationScope, Operation,
+            PackageId, TransactionEvent,
+        },
+        runtimes::{metadata_keyfile, metadata_value},
+    },
+    error::{required, Result},
+};
+
+/// An installed app that is end-of-life itself or runs on a runtime that is.
+#[derive(Debug, Clone)]
+pub struct EolNotice {
+    pub app: PackageId,
+    pub scope: InstallationScope,
+    /// The ref that is end-of-life, the app's own or its runtime's
+    pub eol_ref: String,
+    pub reason: String,
+    /// The ref the app was renamed to, only given for the app's own ref
+    pub rebase: Option<String>,
+    /// Where the app came from, the rebase target is installed from there
+    pub remote: String,
+}
+
+impl EolNotice {
+    /// Whether the runtime rather than the app itself is end-of-life.
+    pub fn is_runtime(&self) -> bool {
+        self.eol_ref.starts_with("runtime/")
+    }
+
+    /// The id the app was renamed to.
+    pub fn rebase_name(&self) -> Option<&str> {
+        self.rebase
+            .as_deref()
+            .map(|rebase| rebase.split('/').nth(1).unwrap_or(rebase))
+    }
+}
+
+/// Full ref -> reason and rebase target of the installed refs.
+type EolInfo = HashMap<String, (Option<String>, Option<String>)>;
+
+/// (remote, full ref) -> reason and rebase target, as the remotes currently publish them. Two
+/// remotes can publish the same ref, only the one it was installed from speaks for it.
+type RemoteEolInfo = HashMap<(String, String), (Option<String>, Option<String>)>;
+
+fn remote_eol_info(
+    installation: &Installation,
+    remotes: &HashSet<String>,
+    cancellable: &Cancellable,
+) -> RemoteEolInfo {
+    let mut info = RemoteEolInfo::new();
+    for remote in remotes {
+        let refs = match installation.list_remote_refs_sync(remote, Some(cancellable)) {
+            Ok(refs) => refs,
+            Err(err) => {
+                eprintln!("Unable to list refs of {}: {}", remote, err);
+                continue;
+            }
+        };
+        for pkg in refs {
+            if let Some(ref_) = pkg.format_ref() {
+                info.insert(
+                    (remote.clone(), ref_.to_string()),
+                    (
+                        pkg.eol().map(|e| e.to_string()),
+                        pkg.eol_rebase().map(|e| e.to_string()),
+                    ),
+                );
+            }
+        }
+    }
+    info
+}
+
+/// Checks the installed apps and their runtimes of all installations for end-of-life markers.
+/// The remotes are asked first, the markers stored at install time are the fallback for refs a
+/// remote does not list anymore.
+pub fn check_eol(cancellable: &Cancellable) -> Result<Vec<EolNotice>> {
+    println!("Checking for end-of-life refs");
+    let mut notices = vec![];
+    for installation in installations()? {
+        let scope = InstallationScope::of(&installation);
+        let refs = installation.list_installed_refs(Some(cancellable))?;
+        let remotes: HashSet<String> = refs
+            .iter()
+            .filter_map(|pkg| pkg.origin().map(|o| o.to_string()))
+            .collect();
+        let remote_info = remote_eol_info(&installation, &remotes, cancellable);
+
+        let mut eol = EolInfo::new();
+        for pkg in refs.iter() {
+            let ref_ = required(pkg.format_ref(), "ref")?;
+            let origin = pkg.origin().map(|o| o.to_string()).unwrap_or_default();
+            let (reason, rebase) = remote_info
+                .get(&(origin, ref_.clone()))
+                .cloned()
+                .unwrap_or((
+                    pkg.eol().map(|e| e.to_string()),
+                    pkg.eol_rebase().map(|e| e.to_string()),
+                ));
+            if reason.is_some() || rebase.is_some() {
+                eol.insert(ref_, (reason, rebase));
+            }
+        }
+
+        for pkg in refs.iter().filter(|pkg| pkg.kind() == RefKind::App) {
+            let app = required(pkg.name(), "name")?;
+            let ref_ = required(pkg.format_ref(), "ref")?;
+            let remote = pkg.origin().map(|o| o.to_string()).unwrap_or_default();
+            if let Some((reason, rebase)) = eol.get(&ref_) {
+                notices.push(EolNotice {
+                    app: app.clone(),
+                    scope: scope.clone(),
+                    eol_ref: ref_.clone(),
+                    reason: reason
+                        .clone()
+                        .unwrap_or("This app is no longer maintained".into()),
+                    rebase: rebase.as_ref().map(|r| rebase_ref(r, &ref_)),
+                    remote: remote.clone(),
+                });
+            }
+            if let Some(runtime) = metadata_keyfile(pkg, cancellable)
+                .and_then(|metadata| metadata_value(&metadata, "Application", "runtime"))
+                .map(|runtime| format!("runtime/{}", runtime))
+            {
+                if let Some((reason, _)) = eol.get(&runtime) {
+                    notices.push(EolNotice {
+                        app,
+                        scope: scope.clone(),
+                        reason: reason
+                            .clone()
+                            .unwrap_or("Its runtime is no longer maintained".into()),
+                        eol_ref: runtime,
+                        rebase: None,
+                        remote,
+                    });
+                }
+            }
+        }
+    }
+    println!("Found {} end-of-life refs", notices.len());
+    Ok(notices)
+}
+
+/// Remotes usually publish the full ref of the rebase target, a bare id takes the arch and
+/// branch of the ref it replaces.
+fn rebase_ref(rebase: &str, old_ref: &str) -> String {
+    if rebase.contains('/') {
+        return rebase.to_string();
+    }
+    let mut parts: Vec<&str> = old_ref.split('/').collect();
+    if parts.len() > 1 {
+        parts[1] = rebase;
+    }
+    parts.join("/")
+}
+
+/// Installs the rebase target of `notice` in place of the app, like `flatpak update` does for
+/// renamed apps. The old id is handed on so the app's data moves along.
+pub fn migrate(
+    notice: &EolNotice,
+    operation: &Operation,
+    tx: mpsc::Sender<TransactionEvent>,
+) -> Result<()> {
+    let rebase = required(notice.rebase.as_ref(), "rebase")?;
+    let installed = find_installed_ref(&notice.app, &notice.scope, &operation.cancellable)?;
+    let old_ref = required(installed.format_ref(), "ref")?;
+    let installation = notice.scope.installation()?;
+    let t = Transaction::for_installation(&installation, Some(&operation.cancellable))?;
+    watch_transaction(&t, operation.id, &tx);
+    t.add_rebase(&notice.remote, &rebase, &[], &[notice.app.as_str()])?;
+    t.add_uninstall(&old_ref)?;
+    println!(
+        "Added the transaction: rebase {} to {} from {}",
+        old_ref, rebase, notice.remote
+    );
+    let res = t.run(Some(&operation.cancellable));
+    println!("Finished the transaction: {:?}", res);
+    Ok(res?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_a_full_rebase_ref() {
+        assert_eq!(
+            rebase_ref(
+                "app/org.gnome.Loupe/x86_64/stable",
+                "app/org.gnome.eog/x86_64/stable"
+            ),
+            "app/org.gnome.Loupe/x86_64/stable"
+        );
+        assert_eq!(
+            rebase_ref(
+                "app/org.gnome.Loupe/aarch64/beta",
+                "app/org.gnome.eog/x86_64/stable"
+            ),
+            "app/org.gnome.Loupe/aarch64/beta"
+        );
+    }
+
+    #[test]
+    fn completes_a_bare_rebase_id() {
+        assert_eq!(
+            rebase_ref("org.gnome.Loupe", "app/org.gnome.eog/x86_64/stable"),
+            "app/org.gnome.Loupe/x86_64/stable"
+        );
+        assert_eq!(
+            rebase_ref(
+                "org.gnome.Platform",
+                "runtime/org.gnome.Platform.Old/aarch64/44"
+            ),
+            "runtime/org.gnome.Platform/aarch64/44"
+        );
+    }
+}
@@ -2,15 +2,20 @@ use appstream::{enums::Bundle, AppId, Collection, Component};
 use iced::futures::channel::mpsc;
 use libflatpak::{
     gio::{traits::FileExt, Cancellable},
-    glib::GString,
+    glib::{self, GString},
     prelude::*,
     traits::{InstallationExt, InstalledRefExt, RefExt, RemoteExt, RemoteRefExt},
-    Installation, InstalledRef, RefKind, RemoteRef, Transaction,
+    Installation, InstalledRef, RefKind, Remote, RemoteRef, Transaction, TransactionOperation,
+    TransactionOperationType, TransactionProgress,
 };
 
 use std::path::PathBuf;
 
-use crate::db::{self, Storage};
+use crate::{
+    backend::{details::PackageDetails, masks},
+    db::{self, Storage},
+    error::{required, BazaarError, Result},
+};
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub enum PackageKind {
@@ -30,13 +35,15 @@ impl PackageKind {
     }
 }
 
-impl From<String> for PackageKind {
-    fn from(value: String) -> Self {
+impl TryFrom<String> for PackageKind {
+    type Error = BazaarError;
+
+    fn try_from(value: String) -> Result<Self> {
         match value.as_str() {
-            "App" => Self::App,
-            "Runtime" => Self::Runtime,
-            "Extension" => Self::Extension,
-            _ => unreachable!(),
+            "App" => Ok(Self::App),
+            "Runtime" => Ok(Self::Runtime),
+            "Extension" => Ok(Self::Extension),
+            _ => Err(BazaarError::UnknownPackageKind(value)),
         }
     }
 }
@@ -53,6 +60,161 @@ impl From<RefKind> for PackageKind {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationKind {
+    Install,
+    Update,
+    Uninstall,
+    InstallBundle,
+}
+
+impl OperationKind {
+    pub fn to_string(&self) -> String {
+        match self {
+            OperationKind::Install => "Installing".into(),
+            OperationKind::Update => "Updating".into(),
+            OperationKind::Uninstall => "Uninstalling".into(),
+            OperationKind::InstallBundle => "Installing bundle".into(),
+        }
+    }
+}
+
+impl From<TransactionOperationType> for OperationKind {
+    fn from(value: TransactionOperationType) -> Self {
+        match value {
+            TransactionOperationType::Update => OperationKind::Update,
+            TransactionOperationType::Uninstall => OperationKind::Uninstall,
+            TransactionOperationType::InstallBundle => OperationKind::InstallBundle,
+            _ => OperationKind::Install,
+        }
+    }
+}
+
+pub type OperationId = u64;
+
+/// An in-flight transaction, the UI refers to it by `id` when cancelling it.
+#[derive(Debug, Clone)]
+pub struct Operation {
+    pub id: OperationId,
+    pub cancellable: Cancellable,
+}
+
+impl Operation {
+    pub fn new(id: OperationId) -> Self {
+        Self {
+            id,
+            cancellable: Cancellable::new(),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellable.is_cancelled()
+    }
+}
+
+/// Snapshot of a single operation of a running transaction.
+#[derive(Debug, Clone)]
+pub struct OperationProgress {
+    pub operation: OperationId,
+    pub kind: OperationKind,
+    pub ref_: String,
+    pub bytes_transferred: u64,
+    pub percent: u32,
+}
+
+impl OperationProgress {
+    /// The app id part of the ref, i.e. `org.foo.Bar` for `app/org.foo.Bar/x86_64/stable`.
+    pub fn package_id(&self) -> PackageId {
+        self.ref_
+            .split('/')
+            .nth(1)
+            .unwrap_or(&self.ref_)
+            .to_string()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TransactionEvent {
+    NewOperation(OperationProgress),
+    Progress(OperationProgress),
+    OperationDone(OperationProgress),
+    OperationError((OperationProgress, String)),
+}
+
+/// Which flatpak installation a package lives in.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub enum InstallationScope {
+    #[default]
+    User,
+    System,
+    /// A named system installation configured in `/etc/flatpak/installations.d`
+    Custom(String),
+}
+
+impl InstallationScope {
+    pub fn of(installation: &Installation) -> Self {
+        if installation.is_user() {
+            return InstallationScope::User;
+        }
+        match installation.id().map(|id| id.to_string()) {
+            Some(id) if id != "default" => InstallationScope::Custom(id),
+            _ => InstallationScope::System,
+        }
+    }
+
+    pub fn installation(&self) -> Result<Installation> {
+        let installation = match self {
+            InstallationScope::User => Installation::new_user(Cancellable::NONE),
+            InstallationScope::System => Installation::new_system(Cancellable::NONE),
+            InstallationScope::Custom(id) => {
+                Installation::new_system_with_id(Some(id), Cancellable::NONE)
+            }
+        };
+        Ok(installation?)
+    }
+
+    /// The option selecting this installation on the `flatpak` command line.
+    pub fn cli_option(&self) -> String {
+        match self {
+            InstallationScope::User => "--user".into(),
+            InstallationScope::System => "--system".into(),
+            InstallationScope::Custom(id) => format!("--installation={}", id),
+        }
+    }
+
+    /// `user`, `system` or the id of a custom installation, as written to manifests.
+    pub fn id(&self) -> String {
+        match self {
+            InstallationScope::User => "user".into(),
+            InstallationScope::System => "system".into(),
+            InstallationScope::Custom(id) => id.clone(),
+        }
+    }
+
+    pub fn from_id(id: &str) -> Self {
+        match id {
+            "user" => InstallationScope::User,
+            "system" | "default" => InstallationScope::System,
+            id => InstallationScope::Custom(id.to_string()),
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        match self {
+            InstallationScope::User => "User".into(),
+            InstallationScope::System => "System".into(),
+            InstallationScope::Custom(id) => id.clone(),
+        }
+    }
+}
+
+/// The user installation followed by every system installation, the default one included.
+pub fn installations() -> Result<Vec<Installation>> {
+    let mut result = vec![Installation::new_user(Cancellable::NONE)?];
+    result.extend(libflatpak::system_installations(Cancellable::NONE)?);
+    Ok(result)
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct Package {
     pub name: PackageId,
@@ -61,48 +223,78 @@ pub struct Package {
     pub summary: Option<String>,
     pub icon_path: Option<PathBuf>,
     pub kind: PackageKind,
+    pub scope: InstallationScope,
+    /// Name of the remote the package comes from
+    pub remote: Option<String>,
+    pub branch: Option<String>,
+    /// Version from the appstream data of an installed app
+    pub version: Option<String>,
+    /// In bytes, only known for packages from a remote
+    pub download_size: Option<u64>,
+    /// In bytes
+    pub installed_size: Option<u64>,
+    /// Masked in its installation, so that updates are held back
+    pub held: bool,
 }
 
-impl From<InstalledRef> for Package {
-    fn from(pkg: InstalledRef) -> Self {
+impl TryFrom<InstalledRef> for Package {
+    type Error = BazaarError;
+
+    fn try_from(pkg: InstalledRef) -> Result<Self> {
         let pretty_name = pkg.appdata_name().map(|s| s.to_string());
-        let remote: String = pkg.origin().unwrap().to_string();
-        let name: String = pkg.name().unwrap().to_string();
-        let arch = pkg.arch().unwrap().to_string();
+        let remote = required(pkg.origin(), "origin")?;
+        let name = required(pkg.name(), "name")?;
+        let arch = required(pkg.arch(), "arch")?;
         let icon_path = get_icon_path(&name, &remote, &arch);
         let kind = PackageKind::from(pkg.kind());
         let summary = pkg.appdata_summary().map(|s| s.to_string());
         let description = None;
 
-        Package {
+        Ok(Package {
             name,
             pretty_name,
             description,
             summary,
             icon_path,
             kind,
-        }
+            scope: InstallationScope::default(),
+            remote: Some(remote),
+            branch: pkg.branch().map(|b| b.to_string()),
+            version: pkg.appdata_version().map(|v| v.to_string()),
+            download_size: None,
+            installed_size: Some(pkg.installed_size()),
+            held: false,
+        })
     }
 }
 
-impl From<RemoteRef> for Package {
-    fn from(pkg: RemoteRef) -> Self {
+impl TryFrom<RemoteRef> for Package {
+    type Error = BazaarError;
+
+    fn try_from(pkg: RemoteRef) -> Result<Self> {
         let pretty_name = pkg.name().map(|s| s.to_string());
-        let remote: String = pkg.remote_name().unwrap().to_string();
-        let name: String = pkg.name().unwrap().to_string();
-        let arch = pkg.arch().unwrap().to_string();
+        let remote = required(pkg.remote_name(), "remote name")?;
+        let name = required(pkg.name(), "name")?;
+        let arch = required(pkg.arch(), "arch")?;
         let kind = PackageKind::from(pkg.kind());
         let description = None;
         let summary = None;
         let icon_path = get_icon_path(&name, &remote, &arch);
-        Package {
+        Ok(Package {
             name,
             pretty_name,
             description,
             summary,
             icon_path,
             kind,
-        }
+            scope: InstallationScope::default(),
+            remote: Some(remote),
+            branch: pkg.branch().map(|b| b.to_string()),
+            version: None,
+            download_size: Some(pkg.download_size()),
+            installed_size: Some(pkg.installed_size()),
+            held: false,
+        })
     }
 }
 
@@ -122,6 +314,13 @@ impl Package {
             summary,
             icon_path,
             kind,
+            scope: InstallationScope::default(),
+            remote: None,
+            branch: None,
+            version: None,
+            download_size: None,
+            installed_size: None,
+            held: false,
         }
     }
 
@@ -134,197 +333,548 @@ impl Package {
         self.summary = summary;
         self
     }
+
+    pub fn with_scope(mut self, scope: InstallationScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    pub fn with_remote(mut self, remote: Option<String>) -> Self {
+        self.remote = remote;
+        self
+    }
+
+    pub fn with_branch(mut self, branch: Option<String>) -> Self {
+        self.branch = branch;
+        self
+    }
+
+    pub fn with_sizes(mut self, download_size: Option<u64>, installed_size: Option<u64>) -> Self {
+        self.download_size = download_size;
+        self.installed_size = installed_size;
+        self
+    }
+
+    pub fn with_held(mut self, held: bool) -> Self {
+        self.held = held;
+        self
+    }
+
+    /// E.g. `12.3 MB download · 40.1 MB installed`, or only the disk usage of an installed app.
+    pub fn size_summary(&self) -> Option<String> {
+        match (self.download_size, self.installed_size) {
+            (Some(download), Some(installed)) => Some(format!(
+                "{} download · {} installed",
+                glib::format_size(download),
+                glib::format_size(installed)
+            )),
+            (None, Some(installed)) => Some(format!("{} on disk", glib::format_size(installed))),
+            (Some(download), None) => Some(format!("{} download", glib::format_size(download))),
+            (None, None) => None,
+        }
+    }
 }
 
-pub fn get_installed_apps() -> Vec<Package> {
+pub fn get_installed_apps() -> Result<Vec<Package>> {
     println!("Getting installed packages");
     let mut result = vec![];
-    // let sys = Installation::new_system(libflatpak::gio::Cancellable::NONE).unwrap();
-    // // TODO: No installed sys packages on my system, will do this later
-    // let installed_sys = sys
-    //     .list_installed_refs(libflatpak::gio::Cancellable::NONE)
-    //     .unwrap();
-    // for pkg in installed_sys {
-    //     println!("sys: {:?}", pkg);
-    // }
-    // User packages
-    let user = Installation::new_user(libflatpak::gio::Cancellable::NONE).unwrap();
-    let installed_user = user
-        .list_installed_refs(libflatpak::gio::Cancellable::NONE)
-        .unwrap();
-    for pkg in installed_user {
-        if pkg.kind() == RefKind::App {
-            result.push(Package::from(pkg));
+    for installation in installations()? {
+        let scope = InstallationScope::of(&installation);
+        let masked = masks::masked_patterns_or_none(&scope, &Cancellable::new());
+        for pkg in installation.list_installed_refs(Cancellable::NONE)? {
+            if pkg.kind() == RefKind::App {
+                let held = pkg
+                    .format_ref()
+                    .map_or(false, |r| masks::is_masked(&masked, &r));
+                match Package::try_from(pkg) {
+                    Ok(package) => result.push(package.with_scope(scope.clone()).with_held(held)),
+                    Err(err) => eprintln!("Skipping installed ref: {}", err),
+                }
+            }
         }
     }
-    result
+    Ok(result)
 }
 
-pub fn get_updatable_apps() -> Vec<Package> {
+pub fn get_updatable_apps(cancellable: &Cancellable) -> Result<Vec<Package>> {
     println!("Getting updatable packages");
     let mut result = vec![];
-    // let sys = Installation::new_system(libflatpak::gio::Cancellable::NONE).unwrap();
-    // // TODO: No installed sys packages on my system, will do this later
-    // let installed_sys = sys
-    //     .list_installed_refs(libflatpak::gio::Cancellable::NONE)
-    //     .unwrap();
-    // for pkg in installed_sys {
-    //     println!("sys: {:?}", pkg);
-    // }
-    // User packages
-    let user = Installation::new_user(libflatpak::gio::Cancellable::NONE).unwrap();
-    let updates_user = user
-        .list_installed_refs_for_update(libflatpak::gio::Cancellable::NONE)
-        .unwrap();
-    for pkg in updates_user {
-        if pkg.kind() == RefKind::App {
-            result.push(Package::from(pkg));
+    for installation in installations()? {
+        let scope = InstallationScope::of(&installation);
+        let masked = masks::masked_patterns_or_none(&scope, cancellable);
+        for pkg in installation.list_installed_refs_for_update(Some(cancellable))? {
+            if pkg.kind() == RefKind::App {
+                // Listed as held rather than as an update that would never be applied
+                let held = pkg
+                    .format_ref()
+                    .map_or(false, |r| masks::is_masked(&masked, &r));
+                match Package::try_from(pkg) {
+                    Ok(package) => result.push(package.with_scope(scope.clone()).with_held(held)),
+                    Err(err) => eprintln!("Skipping updatable ref: {}", err),
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+pub fn uninstall(
+    name: &str,
+    scope: &InstallationScope,
+    operation: &Operation,
+    tx: mpsc::Sender<TransactionEvent>,
+) -> Result<()> {
+    let pkg = find_installed_ref(name, scope, &operation.cancellable)?;
+    let installation = scope.installation()?;
+    let t = Transaction::for_installation(&installation, Some(&operation.cancellable))?;
+    watch_transaction(&t, operation.id, &tx);
+    let res = t.add_uninstall(&required(pkg.format_ref(), "ref")?);
+    println!("Added the transaction: {:?}", res);
+    let res = t.run(Some(&operation.cancellable));
+    println!("Finished the transaction: {:?}", res);
+    Ok(res?)
+}
+
+fn operation_progress(
+    id: OperationId,
+    operation: &TransactionOperation,
+    progress: Option<&TransactionProgress>,
+) -> OperationProgress {
+    OperationProgress {
+        operation: id,
+        kind: OperationKind::from(operation.operation_type()),
+        ref_: operation.ref_().map(|r| r.to_string()).unwrap_or_default(),
+        bytes_transferred: progress.map_or(0, |p| p.bytes_transferred()),
+        percent: progress.map_or(0, |p| p.progress().clamp(0, 100) as u32),
+    }
+}
+
+/// Forwards the operation signals of `t` to `tx` so the UI can follow along.
+pub(crate) fn watch_transaction(
+    t: &Transaction,
+    id: OperationId,
+    tx: &mpsc::Sender<TransactionEvent>,
+) {
+    let progress_tx = tx.clone();
+    t.connect_new_operation(move |_, operation, progress| {
+        let _ = progress_tx
+            .clone()
+            .try_send(TransactionEvent::NewOperation(operation_progress(
+                id,
+                operation,
+                Some(progress),
+            )));
+        progress.set_update_frequency(200);
+        let operation = operation.clone();
+        let progress_tx = progress_tx.clone();
+        progress.connect_changed(move |progress| {
+            let _ = progress_tx
+                .clone()
+                .try_send(TransactionEvent::Progress(operation_progress(
+                    id,
+                    &operation,
+                    Some(progress),
+                )));
+        });
+    });
+    let done_tx = tx.clone();
+    t.connect_operation_done(move |_, operation, _commit, _result| {
+        let mut done = operation_progress(id, operation, None);
+        done.percent = 100;
+        let _ = done_tx
+            .clone()
+            .try_send(TransactionEvent::OperationDone(done));
+    });
+    let error_tx = tx.clone();
+    t.connect_operation_error(move |_, operation, error, _details| {
+        let _ = error_tx.clone().try_send(TransactionEvent::OperationError((
+            operation_progress(id, operation, None),
+            error.to_string(),
+        )));
+        // Abort the rest of the transaction
+        false
+    });
+}
+
+pub fn install(
+    ref_: &str,
+    remote: &str,
+    scope: &InstallationScope,
+    operation: &Operation,
+    tx: mpsc::Sender<TransactionEvent>,
+) -> Result<()> {
+    let installation = scope.installation()?;
+    let t = Transaction::for_installation(&installation, Some(&operation.cancellable))?;
+    watch_transaction(&t, operation.id, &tx);
+    t.add_install(remote, ref_, &[])?;
+    println!("Added the transaction: install {} from {}", ref_, remote);
+    let res = t.run(Some(&operation.cancellable));
+    println!("Finished the transaction: {:?}", res);
+    Ok(res?)
+}
+
+/// Looks `name` up in the remotes and installs it into the installation of that remote.
+pub fn install_by_name(
+    name: &str,
+    operation: &Operation,
+    tx: mpsc::Sender<TransactionEvent>,
+) -> Result<()> {
+    let (remote_ref, scope) = get_remote_ref_by_name(name, &operation.cancellable)?;
+    let ref_ = required(remote_ref.format_ref(), "ref")?;
+    let remote = required(remote_ref.remote_name(), "remote name")?;
+    install(&ref_, &remote, &scope, operation, tx)
+}
+
+/// Refs with a pending update in every installation, runtimes and extensions included.
+pub fn pending_updates(cancellable: &Cancellable) -> Result<Vec<(InstallationScope, Vec<String>)>> {
+    let mut result = vec![];
+    for installation in installations()? {
+        let scope = InstallationScope::of(&installation);
+        // Unlike the listings this fails when the masks can't be read, updating a masked app
+        // would undo its pin
+        let masked = masks::masked_patterns(&scope, cancellable)?;
+        let refs = installation
+            .list_installed_refs_for_update(Some(cancellable))?
+            .iter()
+            .filter_map(|pkg| pkg.format_ref().map(|r| r.to_string()))
+            .filter(|r| !masks::is_masked(&masked, r))
+            .collect::<Vec<_>>();
+        result.push((scope, refs));
+    }
+    Ok(result)
+}
+
+pub fn find_installed_ref(
+    name: &str,
+    scope: &InstallationScope,
+    cancellable: &Cancellable,
+) -> Result<InstalledRef> {
+    let installation = scope.installation()?;
+    installation
+        .list_installed_refs(Some(cancellable))?
+        .into_iter()
+        .find(|pkg| pkg.kind() == RefKind::App && pkg.name().map_or(false, |n| n == name))
+        .ok_or_else(|| BazaarError::NotFound(format!("Installed app {}", name)))
+}
+
+/// Starts the installed app `name` from the first installation that has it.
+pub fn launch(name: &str, cancellable: &Cancellable) -> Result<()> {
+    for installation in installations()? {
+        let scope = InstallationScope::of(&installation);
+        if let Ok(installed) = find_installed_ref(name, &scope, cancellable) {
+            println!("Launching {:?}", installed.format_ref());
+            installation.launch(
+                name,
+                installed.arch().as_deref(),
+                installed.branch().as_deref(),
+                None,
+                Some(cancellable),
+            )?;
+            return Ok(());
         }
     }
-    result
-}
-
-pub fn uninstall(name: &str) {
-    let user = Installation::new_user(libflatpak::gio::Cancellable::NONE).unwrap();
-    let installed_user = user
-        .list_installed_refs(libflatpak::gio::Cancellable::NONE)
-        .unwrap();
-    for pkg in installed_user {
-        if pkg.kind() == RefKind::App {
-            if pkg.name().unwrap_or("".into()).to_string() == name {
-                let t = Transaction::for_installation(
-                    user.as_ref() as &Installation,
-                    Cancellable::NONE,
-                )
-                .unwrap();
-                let res = t.add_uninstall(pkg.format_ref().unwrap().as_str());
-                println!("Added the transaction: {:?}", res);
-                let res = t.run(Cancellable::NONE);
-                println!("Finished the transaction: {:?}", res);
-                break;
-            }
+    Err(BazaarError::NotFound(format!("Installed app {}", name)))
+}
+
+/// Updates all `refs` of the installation in a single transaction.
+pub fn update(
+    refs: &[String],
+    scope: &InstallationScope,
+    operation: &Operation,
+    tx: mpsc::Sender<TransactionEvent>,
+) -> Result<()> {
+    let installation = scope.installation()?;
+    let t = Transaction::for_installation(&installation, Some(&operation.cancellable))?;
+    watch_transaction(&t, operation.id, &tx);
+    for ref_ in refs {
+        t.add_update(ref_, &[], None)?;
+    }
+    println!("Added the transaction: update {:?}", refs);
+    let res = t.run(Some(&operation.cancellable));
+    println!("Finished the transaction: {:?}", res);
+    Ok(res?)
+}
+
+pub fn update_by_name(
+    name: &str,
+    scope: &InstallationScope,
+    operation: &Operation,
+    tx: mpsc::Sender<TransactionEvent>,
+) -> Result<Vec<String>> {
+    let pkg = find_installed_ref(name, scope, &operation.cancellable)?;
+    let refs = vec![required(pkg.format_ref(), "ref")?];
+    update(&refs, scope, operation, tx)?;
+    Ok(refs)
+}
+
+/// Applies every pending update, one transaction per installation.
+pub fn update_all(
+    operation: &Operation,
+    tx: mpsc::Sender<TransactionEvent>,
+) -> Result<Vec<String>> {
+    let mut updated = vec![];
+    for (scope, refs) in pending_updates(&operation.cancellable)? {
+        if refs.is_empty() {
+            continue;
         }
+        update(&refs, &scope, operation, tx.clone())?;
+        updated.extend(refs);
     }
+    Ok(updated)
 }
 
-pub fn get_remote_ref_by_name(name: &str) -> Option<RemoteRef> {
-    let user = Installation::new_user(libflatpak::gio::Cancellable::NONE).unwrap();
-    let remotes = user.list_remotes(Cancellable::NONE).unwrap();
-    for remote in remotes {
-        println!("remote: {:?}", remote);
-        let url = remote.url().unwrap().to_string();
-        let remote_name = remote.name().unwrap().to_string();
-        println!("Load remote {}: {}", remote_name, url);
-        let packages = user
-            .list_remote_refs_sync(&remote_name, Cancellable::NONE)
-            .unwrap();
-        for pkg in &packages {
-            // println!("Ref: {}", pkg.name().unwrap().to_string());
-            if pkg.name().unwrap().to_string().as_str() == name {
-                return Some(pkg.to_owned());
-            }
+/// Every app ref named `name` for this arch, across the enabled remotes of all installations.
+fn remote_refs_named(
+    name: &str,
+    cancellable: &Cancellable,
+) -> Result<Vec<(RemoteRef, InstallationScope)>> {
+    let mut result = vec![];
+    for installation in installations()? {
+        let scope = InstallationScope::of(&installation);
+        let remotes = installation.list_remotes(Some(cancellable))?;
+        for remote in remotes.iter().filter(|r| !r.is_disabled()) {
+            let Some(remote_name) = remote.name().map(|n| n.to_string()) else {
+                continue;
+            };
+            let packages = match installation.list_remote_refs_sync(&remote_name, Some(cancellable))
+            {
+                Ok(packages) => packages,
+                Err(err) => {
+                    eprintln!("Unable to list refs of {}: {}", remote_name, err);
+                    continue;
+                }
+            };
+            result.extend(
+                packages
+                    .into_iter()
+                    .filter(|pkg| {
+                        pkg.kind() == RefKind::App
+                            && pkg.name().map_or(false, |n| n == name)
+                            && pkg.arch().map_or(false, |a| a == std::env::consts::ARCH)
+                    })
+                    .map(|pkg| (pkg, scope.clone())),
+            );
         }
     }
-    None
+    Ok(result)
 }
 
-pub fn get_packages_remote(storage: &Storage, mut tx: mpsc::Sender<db::Message>) {
+/// Finds `name` in the remotes of all installations, the scope tells which installation it
+/// should be installed into. The `stable` branch wins if several remotes or branches have it.
+pub fn get_remote_ref_by_name(
+    name: &str,
+    cancellable: &Cancellable,
+) -> Result<(RemoteRef, InstallationScope)> {
+    let mut refs = remote_refs_named(name, cancellable)?;
+    if refs.is_empty() {
+        return Err(BazaarError::NotFound(format!("{} in any remote", name)));
+    }
+    let stable = refs
+        .iter()
+        .position(|(pkg, _)| pkg.branch().map_or(false, |b| b == "stable"))
+        .unwrap_or(0);
+    Ok(refs.swap_remove(stable))
+}
+
+/// One branch of an app in one remote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefOrigin {
+    pub name: PackageId,
+    pub ref_: String,
+    pub remote: String,
+    pub branch: String,
+    pub scope: InstallationScope,
+    /// Whether this exact branch from this remote is the one installed
+    pub installed: bool,
+}
+
+/// Every branch of `name` available from the remotes, marking the installed ones.
+pub fn list_origins(name: &str, cancellable: &Cancellable) -> Result<Vec<RefOrigin>> {
+    let installed: Vec<(InstallationScope, String, String)> = installations()?
+        .iter()
+        .flat_map(|installation| {
+            let scope = InstallationScope::of(installation);
+            installation
+                .list_installed_refs_by_kind(RefKind::App, Some(cancellable))
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(move |pkg| {
+                    Some((
+                        scope.clone(),
+                        pkg.format_ref()?.to_string(),
+                        pkg.origin()?.to_string(),
+                    ))
+                })
+        })
+        .collect();
+    let mut result = vec![];
+    for (pkg, scope) in remote_refs_named(name, cancellable)? {
+        let ref_ = required(pkg.format_ref(), "ref")?;
+        let remote = required(pkg.remote_name(), "remote name")?;
+        result.push(RefOrigin {
+            name: name.to_string(),
+            installed: installed.contains(&(scope.clone(), ref_.clone(), remote.clone())),
+            branch: required(pkg.branch(), "branch")?,
+            ref_,
+            remote,
+            scope,
+        });
+    }
+    Ok(result)
+}
+
+/// Moves the installed `name` over to `origin`. The same branch from another remote is
+/// reinstalled from there, another branch is installed and the old one removed, all in one
+/// transaction so nothing is lost if it fails half way.
+pub fn switch_origin(
+    origin: &RefOrigin,
+    operation: &Operation,
+    tx: mpsc::Sender<TransactionEvent>,
+) -> Result<()> {
+    let current = find_installed_ref(&origin.name, &origin.scope, &operation.cancellable)?;
+    let current_ref = required(current.format_ref(), "ref")?;
+    let installation = origin.scope.installation()?;
+    let t = Transaction::for_installation(&installation, Some(&operation.cancellable))?;
+    watch_transaction(&t, operation.id, &tx);
+    if current_ref == origin.ref_ {
+        t.set_reinstall(true);
+        t.add_install(&origin.remote, &origin.ref_, &[])?;
+    } else {
+        t.add_install(&origin.remote, &origin.ref_, &[])?;
+        t.add_uninstall(&current_ref)?;
+    }
+    println!(
+        "Added the transaction: switch {} to {} from {}",
+        current_ref, origin.ref_, origin.remote
+    );
+    let res = t.run(Some(&operation.cancellable));
+    println!("Finished the transaction: {:?}", res);
+    Ok(res?)
+}
+
+/// Indexes the appstream data of every remote into `storage`. A remote that fails to load is
+/// reported through `tx` and skipped so the others still get indexed.
+pub fn get_packages_remote(storage: &mut Storage, mut tx: mpsc::Sender<db::Message>) -> Result<()> {
     println!("Refreshing");
 
-    let sys = Installation::new_system(libflatpak::gio::Cancellable::NONE).unwrap();
-    // TODO: No installed sys packages on my system, will do this later
-    let remotes = sys.list_remotes(Cancellable::NONE).unwrap();
-    for remote in remotes {
-        println!("remote: {:?}", remote);
-    }
-    let user = Installation::new_user(libflatpak::gio::Cancellable::NONE).unwrap();
-    let remotes = user.list_remotes(Cancellable::NONE).unwrap();
-    for remote in remotes {
-        println!("remote: {:?}", remote);
-        let url = remote.url().unwrap().to_string();
-        let remote_name = remote.name().unwrap().to_string();
-        println!("Load remote {}: {}", remote_name, url);
-        let packages = user
-            .list_remote_refs_sync(&remote_name, Cancellable::NONE)
-            .unwrap();
-        let total_package = packages.len();
-        let mut appstream_file = PathBuf::new();
-        let appstream_dir = remote.appstream_dir(Some(std::env::consts::ARCH)).unwrap();
-        appstream_file.push(appstream_dir.path().unwrap());
-        appstream_file.push("appstream.xml");
-
-        println!("Parsing appstream xml {:?}", appstream_file);
-        let appdata_collection = match Collection::from_path(appstream_file.clone()) {
-            Ok(collection) => {
-                println!(
-                    "Successfully parsed appstream XML for remote {}",
-                    remote_name
-                );
-                Some(collection)
-            }
-            Err(err) => {
-                eprintln!(
-                    "Unable to parse appstream XML for {:?} : {}",
-                    err.to_string(),
-                    remote_name
-                );
-                None
+    for installation in installations()? {
+        let remotes = installation.list_remotes(Cancellable::NONE)?;
+        // Only browsable remotes belong in the index, see RemoteInfo::is_browsable
+        for remote in remotes
+            .iter()
+            .filter(|r| !r.is_disabled() && !r.is_noenumerate())
+        {
+            println!("remote: {:?}", remote);
+            if let Err(err) = load_remote_packages(storage, &installation, remote, &mut tx) {
+                eprintln!("Unable to load remote {:?}: {}", remote.name(), err);
+                let _ = tx.try_send(db::Message::Error(err));
             }
+        }
+    }
+    Ok(())
+}
+
+pub fn load_remote_packages(
+    storage: &mut Storage,
+    installation: &Installation,
+    remote: &Remote,
+    tx: &mut mpsc::Sender<db::Message>,
+) -> Result<()> {
+    let url = required(remote.url(), "remote url")?;
+    let remote_name = required(remote.name(), "remote name")?;
+    println!("Load remote {}: {}", remote_name, url);
+    let packages = installation.list_remote_refs_sync(&remote_name, Cancellable::NONE)?;
+    let total_package = packages.len();
+    let mut appstream_file = PathBuf::new();
+    let appstream_dir = remote.appstream_dir(Some(std::env::consts::ARCH))?;
+    appstream_file.push(
+        appstream_dir
+            .path()
+            .ok_or(BazaarError::MissingField("appstream dir"))?,
+    );
+    appstream_file.push("appstream.xml");
+
+    println!("Parsing appstream xml {:?}", appstream_file);
+    let appdata_collection = match Collection::from_path(appstream_file.clone()) {
+        Ok(collection) => {
+            println!(
+                "Successfully parsed appstream XML for remote {}",
+                remote_name
+            );
+            Some(collection)
+        }
+        Err(err) => {
+            eprintln!(
+                "Unable to parse appstream XML for {:?} : {}",
+                err.to_string(),
+                remote_name
+            );
+            None
+        }
+    };
+    let mut package_counter = 0;
+    let mut db_packages = Vec::new();
+    for remote_ref in &packages {
+        package_counter += 1;
+        let percentage = ((package_counter * 100) as f32 / total_package as f32) as u32;
+        let _ = tx.try_send(db::Message::Progress(percentage));
+        println!("loaded {percentage:?}%");
+        let (Some(ref_name), Some(name), Some(arch)) = (
+            remote_ref.format_ref().map(|r| r.to_string()),
+            remote_ref.name().map(|n| n.to_string()),
+            remote_ref.arch().map(|a| a.to_string()),
+        ) else {
+            continue;
         };
-        let mut package_counter = 0;
-        let mut db_packages = Vec::new();
-        for remote_ref in &packages {
-            package_counter += 1;
-            let percentage = ((package_counter * 100) as f32 / total_package as f32) as u32;
-            let _ = tx.try_send(db::Message::Progress(percentage));
-            println!("loaded {percentage:?}%");
-            let ref_name = remote_ref.format_ref().unwrap().to_string();
-            // println!("Found package {}", ref_name);
-            let arch = remote_ref.arch().unwrap().to_string();
-            if arch != std::env::consts::ARCH {
-                println!("Not the same arch");
-                continue;
+        // println!("Found package {}", ref_name);
+        if arch != std::env::consts::ARCH {
+            println!("Not the same arch");
+            continue;
+        }
+        let component: Option<Component> = match &appdata_collection {
+            Some(collection) => {
+                let app_id = AppId(name.clone());
+                let components = collection.find_by_id(app_id);
+                components
+                    .into_iter()
+                    .find(|c| get_ref_name(c) == ref_name)
+                    .cloned()
             }
-            let component: Option<Component> = match &appdata_collection {
-                Some(collection) => {
-                    let app_id = AppId(remote_ref.name().unwrap().to_string());
-                    let components = collection.find_by_id(app_id);
-                    components
-                        .into_iter()
-                        .find(|c| get_ref_name(c) == ref_name)
-                        .cloned()
-                }
-                None => {
-                    println!("Unable to find appstream data for {}", ref_name);
-                    None
-                }
-            };
-            if component.is_none() {
-                continue;
+            None => {
+                println!("Unable to find appstream data for {}", ref_name);
+                None
             }
-            let name = remote_ref.name().unwrap().to_string();
-            let (desc, summary, pretty_name) = component.map_or((None, None, None), |c| {
-                (
-                    c.description
-                        .and_then(|d| d.get_default().map(String::to_owned)),
-                    c.summary
-                        .and_then(|d| d.get_default().map(String::to_owned)),
-                    c.name.get_default().map(String::to_owned),
-                )
-            });
-            let icon_path = get_icon_path(&name, &remote.name().unwrap().to_string(), &arch);
-            let package = Package {
-                name,
-                pretty_name,
-                kind: PackageKind::from(remote_ref.kind()),
-                icon_path,
-                description: desc,
-                summary,
-            };
-            db_packages.push(package);
-        }
-        storage.insert_batch(&db_packages).unwrap();
+        };
+        let Some(component) = component else {
+            continue;
+        };
+        let details = PackageDetails::from(&component);
+        let desc = component
+            .description
+            .and_then(|d| d.get_default().map(String::to_owned));
+        let summary = component
+            .summary
+            .and_then(|d| d.get_default().map(String::to_owned));
+        let pretty_name = component.name.get_default().map(String::to_owned);
+        let icon_path = get_icon_path(&name, &remote_name, &arch);
+        let package = Package {
+            name,
+            pretty_name,
+            kind: PackageKind::from(remote_ref.kind()),
+            icon_path,
+            description: desc,
+            summary,
+            scope: InstallationScope::of(installation),
+            remote: Some(remote_name.clone()),
+            branch: remote_ref.branch().map(|b| b.to_string()),
+            version: None,
+            download_size: Some(remote_ref.download_size()),
+            installed_size: Some(remote_ref.installed_size()),
+            held: false,
+        };
+        db_packages.push((package, details));
     }
+    storage.insert_batch(&db_packages)
 }
 
 fn get_ref_name(component: &Component) -> String {
@@ -341,19 +891,23 @@ fn get_ref_name(component: &Component) -> String {
     String::new()
 }
 
+/// Looks for the icon in the user installation first, then in the default system one.
 fn get_icon_path(name: &str, remote: &str, arch: &str) -> Option<PathBuf> {
+    // Read at runtime, flatpak keeps the user installation in `$XDG_DATA_HOME/flatpak`
+    let user_base = glib::user_data_dir().join("flatpak").display().to_string();
+    [user_base.as_str(), "/var/lib/flatpak"]
+        .into_iter()
+        .find_map(|base| get_icon_path_in(base, name, remote, arch))
+}
+
+fn get_icon_path_in(base: &str, name: &str, remote: &str, arch: &str) -> Option<PathBuf> {
     let mut path = {
         let mut path = PathBuf::new();
         for size in [64, 128, 32, 256, 16] {
             let mut tmp_path = PathBuf::new();
             let path_str = format!(
-                "{}/.local/share/flatpak/appstream/{}/{}/active/icons/{}x{}/{}.png",
-                env!("HOME"),
-                remote,
-                arch,
-                size,
-                size,
-                name
+                "{}/appstream/{}/{}/active/icons/{}x{}/{}.png",
+                base, remote, arch, size, size, name
             );
             tmp_path.push(path_str);
             if tmp_path.exists() {
@@ -376,14 +930,9 @@ fn get_icon_path(name: &str, remote: &str, arch: &str) -> Option<PathBuf> {
             ] {
                 let mut tmp_path = PathBuf::new();
                 let path_str = format!(
-                            "{}/.local/share/flatpak/app/{}/current/active/export/share/icons/hicolor/{}x{}/apps/{}.{}",
-                            env!("HOME"),
-                            name,
-                            size.0,
-                            size.0,
-                            name,
-                            size.1,
-                        );
+                    "{}/app/{}/current/active/export/share/icons/hicolor/{}x{}/apps/{}.{}",
+                    base, name, size.0, size.0, name, size.1,
+                );
                 tmp_path.push(path_str);
                 if tmp_path.exists() {
                     path = tmp_path;
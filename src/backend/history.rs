@@ -0,0 +1,172 @@
+use std::process::Command;
+
+use iced::futures::channel::mpsc;
+use libflatpak::{
+    gio::Cancellable,
+    prelude::*,
+    traits::{InstallationExt, InstalledRefExt, RefExt},
+    Transaction,
+};
+
+use crate::{
+    backend::flatpak_backend::{
+        find_installed_ref, watch_transaction, InstallationScope, Operation, TransactionEvent,
+    },
+    error::{required, BazaarError, Result},
+};
+
+/// One commit of a ref on its remote.
+#[derive(Debug, Clone, Default)]
+pub struct Commit {
+    pub commit: String,
+    pub subject: String,
+    pub date: String,
+    /// Whether this is the commit that is installed
+    pub installed: bool,
+}
+
+/// Reads the commit entries of `flatpak remote-info --log`: blank-line separated blocks that
+/// list `Commit:`, `Subject:` and `Date:` among other fields, for the latest commit and, below
+/// `History:`, each of its parents. Blocks without a commit, like the ref's own fields, are skipped.
+fn parse_log(output: &str) -> Vec<Commit> {
+    let mut commits = vec![];
+    let mut entry = Commit::default();
+    // The trailing blank line ends the last entry even if the output has none
+    for line in output.lines().chain([""]) {
+        let line = line.trim();
+        if line.is_empty() {
+            let entry = std::mem::take(&mut entry);
+            if !entry.commit.is_empty() {
+                commits.push(entry);
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key {
+            "Commit" => entry.commit = value,
+            "Subject" => entry.subject = value,
+            "Date" => entry.date = value,
+            _ => {}
+        }
+    }
+    commits
+}
+
+/// The commits of the installed app `name` its remote still knows, newest first. libflatpak does
+/// not expose the commit log, so this asks the `flatpak` command.
+pub fn commit_history(
+    name: &str,
+    scope: &InstallationScope,
+    cancellable: &Cancellable,
+) -> Result<Vec<Commit>> {
+    let installed = find_installed_ref(name, scope, cancellable)?;
+    let ref_ = required(installed.format_ref(), "ref")?;
+    let remote = required(installed.origin(), "origin")?;
+    let current = installed.commit().map(|c| c.to_string());
+    println!("Reading the history of {} from {}", ref_, remote);
+    let output = Command::new("flatpak")
+        .env("LC_ALL", "C")
+        .args(["remote-info", "--log", &scope.cli_option(), &remote, &ref_])
+        .output()?;
+    if !output.status.success() {
+        return Err(BazaarError::Io(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    let mut commits = parse_log(&String::from_utf8_lossy(&output.stdout));
+    for commit in commits.iter_mut() {
+        commit.installed = Some(&commit.commit) == current.as_ref();
+    }
+    Ok(commits)
+}
+
+/// Moves the installed app `name` to `commit`, older or newer than the installed one.
+pub fn rollback(
+    name: &str,
+    scope: &InstallationScope,
+    commit: &str,
+    operation: &Operation,
+    tx: mpsc::Sender<TransactionEvent>,
+) -> Result<()> {
+    let installed = find_installed_ref(name, scope, &operation.cancellable)?;
+    let ref_ = required(installed.format_ref(), "ref")?;
+    let installation = scope.installation()?;
+    let t = Transaction::for_installation(&installation, Some(&operation.cancellable))?;
+    watch_transaction(&t, operation.id, &tx);
+    t.add_update(&ref_, &[], Some(commit))?;
+    println!("Added the transaction: {} to commit {}", ref_, commit);
+    let res = t.run(Some(&operation.cancellable));
+    println!("Finished the transaction: {:?}", res);
+    Ok(res?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Laid out like `LC_ALL=C flatpak remote-info --log flathub app/org.gnome.Maps/x86_64/stable`
+    /// prints it, with the history cut down to three commits.
+    const LOG: &str = "
+        ID: org.gnome.Maps
+       Ref: app/org.gnome.Maps/x86_64/stable
+      Arch: x86_64
+    Branch: stable
+   Version: 45.1
+   License: GPL-2.0-or-later
+Collection: org.flathub.Stable
+  Download: 6.3 MB
+ Installed: 21.9 MB
+   Runtime: org.gnome.Platform/x86_64/45
+       Sdk: org.gnome.Sdk/x86_64/45
+
+    Commit: 3a2b1c64d7e0b6f3c1e8a9d2f4b5c6a7e8f9d0c1b2a3e4f5d6c7b8a9f0e1d2c3
+    Parent: 9f8e7d6c5b4a39281706f5e4d3c2b1a09f8e7d6c5b4a39281706f5e4d3c2b1a0
+   Subject: Update to 45.1 (e3c1f0a2)
+      Date: 2023-10-20 11:22:33 +0000
+
+   History:
+
+    Commit: 9f8e7d6c5b4a39281706f5e4d3c2b1a09f8e7d6c5b4a39281706f5e4d3c2b1a0
+   Subject: Update to 45.0 (a1b2c3d4)
+      Date: 2023-10-01 08:00:00 +0000
+
+    Commit: 0c1d2e3f4a5b6c7d8e9f0a1b2c3d4e5f6a7b8c9d0e1f2a3b4c5d6e7f8a9b0c1d
+   Subject: Export to flathub: 45.rc (77aa33ff)
+      Date: 2023-09-15 17:45:10 +0000
+
+";
+
+    #[test]
+    fn reads_each_commit_of_the_log() {
+        let commits = parse_log(LOG);
+        let ids: Vec<&str> = commits.iter().map(|c| &c.commit[..6]).collect();
+        assert_eq!(ids, ["3a2b1c", "9f8e7d", "0c1d2e"]);
+        assert_eq!(commits[0].subject, "Update to 45.1 (e3c1f0a2)");
+        assert_eq!(commits[0].date, "2023-10-20 11:22:33 +0000");
+        assert_eq!(commits[1].subject, "Update to 45.0 (a1b2c3d4)");
+        assert_eq!(commits[2].subject, "Export to flathub: 45.rc (77aa33ff)");
+        assert_eq!(commits[2].date, "2023-09-15 17:45:10 +0000");
+        assert!(commits.iter().all(|c| !c.installed));
+    }
+
+    #[test]
+    fn reads_the_fields_of_an_entry_in_any_order() {
+        let commits = parse_log(
+            "      Date: 2023-10-01 08:00:00 +0000\n   Subject: Rebuild\n    Commit: 9f8e7d",
+        );
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].commit, "9f8e7d");
+        assert_eq!(commits[0].subject, "Rebuild");
+        assert_eq!(commits[0].date, "2023-10-01 08:00:00 +0000");
+    }
+
+    #[test]
+    fn skips_entries_without_a_commit() {
+        assert!(parse_log("   Subject: Orphan\n      Date: 2023-10-01\n").is_empty());
+        assert!(parse_log("   History:\n\n").is_empty());
+        assert!(parse_log("").is_empty());
+    }
+}
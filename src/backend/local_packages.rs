@@ -0,0 +1,183 @@
+use std::path::{Path, PathBuf};
+
+use iced::futures::channel::mpsc;
+use libflatpak::{
+    gio::{self, traits::FileExt, Cancellable},
+    glib::{self, KeyFile, KeyFileFlags},
+    prelude::*,
+    traits::{BundleRefExt, InstallationExt, RefExt, RemoteExt, RemoteRefExt},
+    BundleRef, RefKind, Transaction,
+};
+
+use crate::{
+    backend::flatpak_backend::{
+        installations, watch_transaction, InstallationScope, Operation, TransactionEvent,
+    },
+    error::{required, BazaarError, Result},
+};
+
+const FLATPAKREF_GROUP: &str = "Flatpak Ref";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocalPackageKind {
+    /// A `.flatpakref` file describing where to fetch the app from
+    Flatpakref,
+    /// A single-file `.flatpak` bundle carrying the app itself
+    Bundle,
+}
+
+/// A `.flatpakref` or `.flatpak` file opened from disk, waiting for the user to confirm the install.
+#[derive(Debug, Clone)]
+pub struct LocalPackage {
+    pub path: PathBuf,
+    pub kind: LocalPackageKind,
+    pub name: String,
+    pub title: Option<String>,
+    pub branch: Option<String>,
+    /// The remote the app is fetched from, or the origin a bundle was built for
+    pub remote: Option<String>,
+    pub runtime: Option<String>,
+    /// Where flatpak looks for the runtime if it isn't installed yet
+    pub runtime_repo: Option<String>,
+    pub scope: InstallationScope,
+}
+
+/// The runtime named in the `[Application]` group of the metadata of an app.
+fn metadata_runtime(metadata: &glib::Bytes) -> Option<String> {
+    let keyfile = KeyFile::new();
+    keyfile.load_from_bytes(metadata, KeyFileFlags::NONE).ok()?;
+    keyfile
+        .string("Application", "runtime")
+        .ok()
+        .map(|r| r.to_string())
+}
+
+/// Looks up the runtime of the app `name` in the metadata its remote publishes. The remote of
+/// a `.flatpakref` is only added by the install, so this finds nothing unless an installation
+/// already has a remote named `remote_name` or with the same `url`.
+fn remote_runtime(
+    remote_name: Option<&str>,
+    url: Option<&str>,
+    name: &str,
+    branch: Option<&str>,
+    cancellable: &Cancellable,
+) -> Option<String> {
+    let same_url = |other: Option<glib::GString>| match (url, other) {
+        (Some(url), Some(other)) => url.trim_end_matches('/') == other.trim_end_matches('/'),
+        _ => false,
+    };
+    for installation in installations().ok()? {
+        let Ok(remotes) = installation.list_remotes(Some(cancellable)) else {
+            continue;
+        };
+        for remote in remotes {
+            let Some(found) = remote
+                .name()
+                .filter(|n| Some(n.as_str()) == remote_name || same_url(remote.url()))
+            else {
+                continue;
+            };
+            match installation.fetch_remote_ref_sync(
+                &found,
+                RefKind::App,
+                name,
+                None,
+                branch,
+                Some(cancellable),
+            ) {
+                Ok(remote_ref) => return remote_ref.metadata().and_then(|m| metadata_runtime(&m)),
+                Err(err) => eprintln!("Unable to look up {} in {}: {}", name, found, err),
+            }
+        }
+    }
+    None
+}
+
+impl LocalPackage {
+    /// Reads the file at `path`. The runtime of a `.flatpakref` comes from its remote, which
+    /// may need the network.
+    pub fn open(path: &Path, cancellable: &Cancellable) -> Result<Self> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("flatpakref") => Self::from_flatpakref(path, cancellable),
+            Some("flatpak") => Self::from_bundle(path),
+            _ => Err(BazaarError::NotFound(format!(
+                "A .flatpakref or .flatpak file at {}",
+                path.display()
+            ))),
+        }
+    }
+
+    fn from_flatpakref(path: &Path, cancellable: &Cancellable) -> Result<Self> {
+        let keyfile = KeyFile::new();
+        keyfile.load_from_file(path, KeyFileFlags::NONE)?;
+        let get = |key| {
+            keyfile
+                .string(FLATPAKREF_GROUP, key)
+                .ok()
+                .map(|v| v.to_string())
+        };
+        let name = required(get("Name"), "Name")?;
+        let branch = get("Branch");
+        let runtime = remote_runtime(
+            get("SuggestRemoteName").as_deref(),
+            get("Url").as_deref(),
+            &name,
+            branch.as_deref(),
+            cancellable,
+        );
+        Ok(Self {
+            path: path.to_path_buf(),
+            kind: LocalPackageKind::Flatpakref,
+            name,
+            title: get("Title"),
+            branch,
+            remote: get("SuggestRemoteName").or(get("Url")),
+            runtime,
+            runtime_repo: get("RuntimeRepo"),
+            scope: Default::default(),
+        })
+    }
+
+    fn from_bundle(path: &Path) -> Result<Self> {
+        let bundle = BundleRef::new(&gio::File::for_path(path))?;
+        let runtime = bundle.metadata().and_then(|m| metadata_runtime(&m));
+        Ok(Self {
+            path: path.to_path_buf(),
+            kind: LocalPackageKind::Bundle,
+            name: required(bundle.name(), "name")?,
+            title: None,
+            branch: bundle.branch().map(|b| b.to_string()),
+            remote: bundle.origin().map(|o| o.to_string()),
+            runtime,
+            runtime_repo: bundle.runtime_repo_url().map(|r| r.to_string()),
+            scope: Default::default(),
+        })
+    }
+}
+
+/// Installs `package` with the same progress reporting as an install from a remote.
+pub fn install_local(
+    package: &LocalPackage,
+    operation: &Operation,
+    tx: mpsc::Sender<TransactionEvent>,
+) -> Result<()> {
+    let installation = package.scope.installation()?;
+    let t = Transaction::for_installation(&installation, Some(&operation.cancellable))?;
+    watch_transaction(&t, operation.id, &tx);
+    let file = gio::File::for_path(&package.path);
+    match package.kind {
+        LocalPackageKind::Flatpakref => {
+            let (contents, _etag) = file.load_contents(Some(&operation.cancellable))?;
+            t.add_install_flatpakref(&glib::Bytes::from_owned(contents.to_vec()))?;
+        }
+        LocalPackageKind::Bundle => t.add_install_bundle(&file, None)?,
+    }
+    println!(
+        "Added the transaction: install {} from {}",
+        package.name,
+        package.path.display()
+    );
+    let res = t.run(Some(&operation.cancellable));
+    println!("Finished the transaction: {:?}", res);
+    Ok(res?)
+}
@@ -0,0 +1,377 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use iced::futures::channel::mpsc;
+use libflatpak::{
+    gio::Cancellable,
+    prelude::*,
+    traits::{InstallationExt, InstalledRefExt, RefExt},
+    Installation, RefKind, Transaction,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::flatpak_backend::{
+        installations, watch_transaction, InstallationScope, Operation, OperationKind,
+        TransactionEvent,
+    },
+    error::{required, BazaarError, Result},
+};
+
+/// One installed app as written to a manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestApp {
+    #[serde(rename = "ref")]
+    pub ref_: String,
+    pub remote: String,
+    pub branch: String,
+    /// `user`, `system` or the id of a custom installation
+    pub installation: String,
+    /// Set if the app should stay at exactly this commit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+impl ManifestApp {
+    pub fn scope(&self) -> InstallationScope {
+        InstallationScope::from_id(&self.installation)
+    }
+
+    /// The app id part of the ref.
+    pub fn name(&self) -> &str {
+        self.ref_.split('/').nth(1).unwrap_or(&self.ref_)
+    }
+}
+
+/// The set of apps of a machine, to set up another one the same way.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub apps: Vec<ManifestApp>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    Toml,
+    Json,
+}
+
+impl ManifestFormat {
+    /// Picks the format by the extension of `path`.
+    pub fn of(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Ok(ManifestFormat::Toml),
+            Some("json") => Ok(ManifestFormat::Json),
+            _ => Err(BazaarError::NotFound(format!(
+                "A .toml or .json manifest at {}",
+                path.display()
+            ))),
+        }
+    }
+}
+
+impl Manifest {
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(match ManifestFormat::of(path)? {
+            ManifestFormat::Toml => toml::from_str(&contents)?,
+            ManifestFormat::Json => serde_json::from_str(&contents)?,
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let contents = match ManifestFormat::of(path)? {
+            ManifestFormat::Toml => toml::to_string_pretty(self)?,
+            ManifestFormat::Json => serde_json::to_string_pretty(self)?,
+        };
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+/// The installed apps of all installations, with their commits if `pin_commits` is set.
+pub fn installed_manifest(pin_commits: bool, cancellable: &Cancellable) -> Result<Manifest> {
+    let mut apps = vec![];
+    for installation in installations()? {
+        let scope = InstallationScope::of(&installation);
+        for pkg in installation.list_installed_refs_by_kind(RefKind::App, Some(cancellable))? {
+            apps.push(ManifestApp {
+                ref_: required(pkg.format_ref(), "ref")?,
+                remote: required(pkg.origin(), "origin")?,
+                branch: required(pkg.branch(), "branch")?,
+                installation: scope.id(),
+                commit: if pin_commits {
+                    pkg.commit().map(|c| c.to_string())
+                } else {
+                    None
+                },
+            });
+        }
+    }
+    Ok(Manifest { apps })
+}
+
+/// Writes the installed apps to `path`, TOML or JSON depending on its extension.
+pub fn export(path: &Path, pin_commits: bool, cancellable: &Cancellable) -> Result<usize> {
+    let manifest = installed_manifest(pin_commits, cancellable)?;
+    println!(
+        "Exporting {} apps to {}",
+        manifest.apps.len(),
+        path.display()
+    );
+    manifest.write(path)?;
+    Ok(manifest.apps.len())
+}
+
+/// One step of the transaction an import would run.
+#[derive(Debug, Clone)]
+pub struct PlannedOperation {
+    pub kind: OperationKind,
+    pub ref_: String,
+    pub remote: String,
+    pub scope: InstallationScope,
+    /// In bytes
+    pub download_size: u64,
+}
+
+/// What importing a manifest would change, worked out before anything is installed.
+#[derive(Debug, Clone, Default)]
+pub struct ImportPlan {
+    pub path: PathBuf,
+    /// The apps of the manifest that are not installed yet
+    pub missing: Vec<ManifestApp>,
+    pub already_installed: Vec<ManifestApp>,
+    /// Apps whose installation does not exist here or lacks their remote
+    pub unavailable: Vec<ManifestApp>,
+    /// The resolved transaction, the runtimes the missing apps need included
+    pub operations: Vec<PlannedOperation>,
+}
+
+impl ImportPlan {
+    pub fn download_size(&self) -> u64 {
+        self.operations.iter().map(|op| op.download_size).sum()
+    }
+}
+
+/// The missing apps of `apps` grouped by the installation they go to.
+fn by_scope(apps: &[ManifestApp]) -> Vec<(InstallationScope, Vec<&ManifestApp>)> {
+    let mut groups: Vec<(InstallationScope, Vec<&ManifestApp>)> = vec![];
+    for app in apps {
+        let scope = app.scope();
+        match groups.iter_mut().find(|(s, _)| *s == scope) {
+            Some((_, group)) => group.push(app),
+            None => groups.push((scope, vec![app])),
+        }
+    }
+    groups
+}
+
+/// Resolves the installs of `apps` without running them. The transaction is stopped once it
+/// is ready, which is after flatpak worked out the dependencies and before it downloads.
+fn preview(
+    installation: &Installation,
+    scope: &InstallationScope,
+    apps: &[&ManifestApp],
+    cancellable: &Cancellable,
+) -> Result<Vec<PlannedOperation>> {
+    let t = Transaction::for_installation(installation, Some(cancellable))?;
+    for app in apps {
+        t.add_install(&app.remote, &app.ref_, &[])?;
+    }
+    let planned: Arc<Mutex<Option<Vec<PlannedOperation>>>> = Default::default();
+    let ready = planned.clone();
+    let ready_scope = scope.clone();
+    t.connect_ready(move |t| {
+        let operations = t
+            .operations()
+            .iter()
+            .map(|op| PlannedOperation {
+                kind: OperationKind::from(op.operation_type()),
+                ref_: op.ref_().map(|r| r.to_string()).unwrap_or_default(),
+                remote: op.remote().map(|r| r.to_string()).unwrap_or_default(),
+                scope: ready_scope.clone(),
+                download_size: op.download_size(),
+            })
+            .collect();
+        *ready.lock().unwrap() = Some(operations);
+        // Abort, this was only a preview
+        false
+    });
+    let res = t.run(Some(cancellable));
+    let planned = planned.lock().unwrap().take();
+    match (planned, res) {
+        (Some(operations), _) => Ok(operations),
+        (None, Err(err)) => Err(err.into()),
+        (None, Ok(())) => Ok(vec![]),
+    }
+}
+
+/// Reads the manifest at `path` and works out what importing it would install.
+pub fn plan_import(path: &Path, cancellable: &Cancellable) -> Result<ImportPlan> {
+    let manifest = Manifest::read(path)?;
+    let mut plan = ImportPlan {
+        path: path.to_path_buf(),
+        ..Default::default()
+    };
+    let mut installed: HashMap<InstallationScope, HashSet<String>> = HashMap::new();
+    for app in manifest.apps {
+        let scope = app.scope();
+        // E.g. a custom installation of the machine the manifest was exported on
+        let installation = match scope.installation() {
+            Ok(installation) => installation,
+            Err(err) => {
+                eprintln!("Skipping {}: {}", app.ref_, err);
+                plan.unavailable.push(app);
+                continue;
+            }
+        };
+        if !installed.contains_key(&scope) {
+            let refs = installation
+                .list_installed_refs(Some(cancellable))?
+                .iter()
+                .filter_map(|pkg| pkg.format_ref().map(|r| r.to_string()))
+                .collect();
+            installed.insert(scope.clone(), refs);
+        }
+        if installed[&scope].contains(&app.ref_) {
+            plan.already_installed.push(app);
+        } else if installation
+            .remote_by_name(&app.remote, Some(cancellable))
+            .is_err()
+        {
+            plan.unavailable.push(app);
+        } else {
+            plan.missing.push(app);
+        }
+    }
+    for (scope, apps) in by_scope(&plan.missing) {
+        let installation = scope.installation()?;
+        plan.operations
+            .extend(preview(&installation, &scope, &apps, cancellable)?);
+    }
+    println!(
+        "Import of {}: {} to install, {} installed, {} unavailable",
+        path.display(),
+        plan.missing.len(),
+        plan.already_installed.len(),
+        plan.unavailable.len()
+    );
+    Ok(plan)
+}
+
+/// Installs the missing apps of `plan`, one transaction per installation. Apps pinned to a
+/// commit are moved to it right after.
+pub fn apply_import(
+    plan: &ImportPlan,
+    operation: &Operation,
+    tx: mpsc::Sender<TransactionEvent>,
+) -> Result<Vec<String>> {
+    let mut installed = vec![];
+    for (scope, apps) in by_scope(&plan.missing) {
+        let installation = scope.installation()?;
+        let t = Transaction::for_installation(&installation, Some(&operation.cancellable))?;
+        watch_transaction(&t, operation.id, &tx);
+        for app in apps.iter() {
+            t.add_install(&app.remote, &app.ref_, &[])?;
+        }
+        println!(
+            "Added the transaction: import {} apps into {}",
+            apps.len(),
+            scope.to_string()
+        );
+        let res = t.run(Some(&operation.cancellable));
+        println!("Finished the transaction: {:?}", res);
+        res?;
+        installed.extend(apps.iter().map(|app| app.ref_.clone()));
+
+        let pinned: Vec<&&ManifestApp> = apps.iter().filter(|app| app.commit.is_some()).collect();
+        if pinned.is_empty() {
+            continue;
+        }
+        let t = Transaction::for_installation(&installation, Some(&operation.cancellable))?;
+        watch_transaction(&t, operation.id, &tx);
+        for app in pinned {
+            t.add_update(&app.ref_, &[], app.commit.as_deref())?;
+        }
+        println!("Added the transaction: move pinned apps to their commits");
+        let res = t.run(Some(&operation.cancellable));
+        println!("Finished the transaction: {:?}", res);
+        res?;
+    }
+    Ok(installed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> Manifest {
+        Manifest {
+            apps: vec![
+                ManifestApp {
+                    ref_: "app/org.gnome.Maps/x86_64/stable".into(),
+                    remote: "flathub".into(),
+                    branch: "stable".into(),
+                    installation: "user".into(),
+                    commit: None,
+                },
+                ManifestApp {
+                    ref_: "app/org.gimp.GIMP/x86_64/beta".into(),
+                    remote: "flathub-beta".into(),
+                    branch: "beta".into(),
+                    installation: "system".into(),
+                    commit: Some("3a2b1c".into()),
+                },
+            ],
+        }
+    }
+
+    /// Writes the manifest to a fresh file named `name` and reads it back.
+    fn round_trip(name: &str) -> (Manifest, String) {
+        let dir = std::env::temp_dir().join(format!("bazaar-manifest-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        manifest().write(&path).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        (Manifest::read(&path).unwrap(), contents)
+    }
+
+    #[test]
+    fn round_trips_toml() {
+        let (read, contents) = round_trip("apps.toml");
+        assert_eq!(read.apps, manifest().apps);
+        assert!(contents.contains("ref = \"app/org.gnome.Maps/x86_64/stable\""));
+        // Only the pinned app carries a commit
+        assert_eq!(contents.matches("commit").count(), 1);
+    }
+
+    #[test]
+    fn round_trips_json() {
+        let (read, contents) = round_trip("apps.json");
+        assert_eq!(read.apps, manifest().apps);
+        assert!(contents.contains("\"ref\": \"app/org.gimp.GIMP/x86_64/beta\""));
+        assert_eq!(contents.matches("commit").count(), 1);
+    }
+
+    #[test]
+    fn picks_the_format_by_extension() {
+        assert_eq!(
+            ManifestFormat::of(Path::new("apps.toml")).unwrap(),
+            ManifestFormat::Toml
+        );
+        assert_eq!(
+            ManifestFormat::of(Path::new("apps.json")).unwrap(),
+            ManifestFormat::Json
+        );
+        assert!(ManifestFormat::of(Path::new("apps.yaml")).is_err());
+    }
+
+    #[test]
+    fn reads_the_app_id_of_a_ref() {
+        assert_eq!(manifest().apps[0].name(), "org.gnome.Maps");
+        assert_eq!(manifest().apps[1].scope(), InstallationScope::System);
+    }
+}
@@ -0,0 +1,161 @@
+use libflatpak::{gio::Cancellable, glib, traits::InstallationExt};
+
+use crate::{backend::flatpak_backend::InstallationScope, error::Result};
+
+/// The installation config key `flatpak mask` keeps its patterns in.
+const MASKED: &str = "masked";
+
+/// The patterns of refs the installation never updates or installs, e.g. `org.foo.Bar`.
+pub fn masked_patterns(
+    scope: &InstallationScope,
+    cancellable: &Cancellable,
+) -> Result<Vec<String>> {
+    let installation = scope.installation()?;
+    match installation.config(MASKED, Some(cancellable)) {
+        Ok(patterns) => Ok(patterns
+            .split(';')
+            .filter(|p| !p.is_empty())
+            .map(str::to_owned)
+            .collect()),
+        Err(err) if is_unset(&err) => Ok(vec![]),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Whether reading a config key failed only because nothing was ever masked: flatpak reports
+/// an unset key like any other error from the repo config.
+fn is_unset(err: &glib::Error) -> bool {
+    err.matches(glib::KeyFileError::KeyNotFound) || err.matches(glib::KeyFileError::GroupNotFound)
+}
+
+/// The masked patterns for a listing, which should not fail over them: when the config can't
+/// be read the error is logged and the installation is listed as if nothing was masked.
+pub fn masked_patterns_or_none(
+    scope: &InstallationScope,
+    cancellable: &Cancellable,
+) -> Vec<String> {
+    masked_patterns(scope, cancellable).unwrap_or_else(|err| {
+        eprintln!(
+            "Unable to read the masked refs of {}: {}",
+            scope.to_string(),
+            err
+        );
+        vec![]
+    })
+}
+
+/// Adds or, with `masked` unset, removes `pattern` like `flatpak mask [--remove]` does. Fails
+/// without writing anything if the current patterns can't be read, which would drop them.
+pub fn set_masked(
+    scope: &InstallationScope,
+    pattern: &str,
+    masked: bool,
+    cancellable: &Cancellable,
+) -> Result<()> {
+    let mut patterns = masked_patterns(scope, cancellable)?;
+    patterns.retain(|p| p != pattern);
+    if masked {
+        patterns.push(pattern.to_string());
+    }
+    println!("Masked patterns of {}: {:?}", scope.to_string(), patterns);
+    scope
+        .installation()?
+        .set_config_sync(MASKED, &patterns.join(";"), Some(cancellable))?;
+    Ok(())
+}
+
+/// Whether `text` matches `pattern`, where `*` stands for any run of characters.
+fn glob(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            let Some(text) = text.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=text.len())
+                .filter(|i| text.is_char_boundary(*i))
+                .any(|i| glob(rest, &text[i..]))
+        }
+    }
+}
+
+/// Whether `ref_` (`kind/name/arch/branch`) matches one of `patterns` the way flatpak matches
+/// them: either a full ref or `name[/arch[/branch]]`, parts left out or empty match anything.
+pub fn is_masked(patterns: &[String], ref_: &str) -> bool {
+    let parts: Vec<&str> = ref_.split('/').collect();
+    patterns.iter().any(|pattern| {
+        let pattern: Vec<&str> = pattern.split('/').collect();
+        let parts = match pattern.first() {
+            Some(&"app") | Some(&"runtime") => &parts[..],
+            _ => &parts[parts.len().min(1)..],
+        };
+        pattern.len() <= parts.len()
+            && pattern
+                .iter()
+                .zip(parts)
+                .all(|(p, part)| p.is_empty() || glob(p, part))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn treats_only_a_missing_key_as_unset() {
+        assert!(is_unset(&glib::Error::new(
+            glib::KeyFileError::KeyNotFound,
+            "Key file does not have key “xa.masked” in group “core”"
+        )));
+        assert!(is_unset(&glib::Error::new(
+            glib::KeyFileError::GroupNotFound,
+            "Key file does not have group “core”"
+        )));
+        assert!(!is_unset(&glib::Error::new(
+            glib::KeyFileError::Parse,
+            "Key file contains line “xa.masked” which is not a key-value pair"
+        )));
+        assert!(!is_unset(&glib::Error::new(
+            glib::FileError::Acces,
+            "Permission denied"
+        )));
+    }
+
+    #[test]
+    fn globs() {
+        assert!(glob("org.gnome.Maps", "org.gnome.Maps"));
+        assert!(!glob("org.gnome.Maps", "org.gnome.Maps2"));
+        assert!(glob("org.gnome.*", "org.gnome.Maps"));
+        assert!(!glob("org.gnome.*", "org.kde.Maps"));
+        assert!(glob("org.*.Maps", "org.gnome.Maps"));
+        assert!(!glob("org.*.Maps", "org.gnome.Weather"));
+        assert!(glob("*", ""));
+        assert!(glob("a*b*c", "aXbYc"));
+        assert!(!glob("a*b*c", "aXcYb"));
+    }
+
+    #[test]
+    fn masks_by_name_arch_and_branch() {
+        let ref_ = "app/org.foo.Bar/x86_64/stable";
+        assert!(is_masked(&patterns(&["org.foo.Bar"]), ref_));
+        assert!(is_masked(&patterns(&["org.foo.*"]), ref_));
+        assert!(is_masked(&patterns(&["org.foo.Bar/x86_64"]), ref_));
+        assert!(is_masked(&patterns(&["org.foo.Bar//stable"]), ref_));
+        assert!(!is_masked(&patterns(&["org.foo.Bar//beta"]), ref_));
+        assert!(!is_masked(&patterns(&["org.foo.Bar/aarch64"]), ref_));
+        assert!(!is_masked(&patterns(&["org.foo.Baz"]), ref_));
+        assert!(!is_masked(&[], ref_));
+    }
+
+    #[test]
+    fn masks_full_refs_of_their_kind_only() {
+        let masked = patterns(&["runtime/org.gnome.Platform/x86_64/45"]);
+        assert!(is_masked(&masked, "runtime/org.gnome.Platform/x86_64/45"));
+        assert!(!is_masked(&masked, "runtime/org.gnome.Platform/x86_64/46"));
+        assert!(!is_masked(&masked, "app/org.gnome.Platform/x86_64/45"));
+    }
+}
@@ -1 +1,15 @@
+pub mod app_data;
+pub mod backups;
+pub mod details;
+pub mod eol;
 pub mod flatpak_backend;
+pub mod history;
+pub mod local_packages;
+pub mod manifest;
+pub mod masks;
+pub mod overrides;
+pub mod permissions;
+pub mod remotes;
+pub mod runtimes;
+pub mod screenshots;
+pub mod sync;
@@ -0,0 +1,368 @@
+use std::path::{Path, PathBuf};
+
+use libflatpak::{
+    gio::{self, traits::FileExt},
+    glib::{KeyFile, KeyFileFlags},
+    traits::InstallationExt,
+};
+
+use crate::{
+    backend::flatpak_backend::{InstallationScope, PackageId},
+    error::{BazaarError, Result},
+};
+
+const CONTEXT: &str = "Context";
+const ENVIRONMENT: &str = "Environment";
+
+/// The `[Context]` keys the editor can change.
+pub const CONTEXT_KEYS: [&str; 4] = ["filesystems", "sockets", "devices", "shared"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverrideState {
+    Granted,
+    Revoked,
+}
+
+/// A change to the overrides of an app, what `flatpak override` does from the command line.
+#[derive(Debug, Clone)]
+pub enum OverrideChange {
+    /// Grants, revokes or, with `None`, stops overriding `value` of the `[Context]` key `key`
+    Context {
+        key: String,
+        value: String,
+        state: Option<OverrideState>,
+    },
+    /// Sets or, with `None`, stops overriding an environment variable
+    Environment { name: String, value: Option<String> },
+    /// Drops every override of the app, like `flatpak override --reset`
+    Reset,
+}
+
+/// The local overrides of an app in one installation.
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    pub app: PackageId,
+    pub scope: InstallationScope,
+    /// `[Context]` entries as `(key, value)`, revoked values start with `!`
+    pub context: Vec<(String, String)>,
+    pub environment: Vec<(String, String)>,
+    /// Whether the override file can be written, the system installation's usually needs root
+    /// and `flatpak override --system` has no way around that either
+    pub writable: bool,
+}
+
+impl Overrides {
+    pub fn state(&self, key: &str, value: &str) -> Option<OverrideState> {
+        self.context.iter().find_map(|(k, v)| {
+            if k != key {
+                None
+            } else if v == value {
+                Some(OverrideState::Granted)
+            } else if v.strip_prefix('!') == Some(value) {
+                Some(OverrideState::Revoked)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// `overrides/<app>` in the directory of the installation.
+fn override_path(app: &str, scope: &InstallationScope) -> Result<PathBuf> {
+    let base = scope
+        .installation()?
+        .path()
+        .and_then(|file| file.path())
+        .ok_or(BazaarError::MissingField("installation path"))?;
+    Ok(base.join("overrides").join(app))
+}
+
+/// Whether `path` can be written or, if it doesn't exist yet, created.
+fn is_writable(path: &Path) -> bool {
+    path.ancestors()
+        .find(|p| p.exists())
+        .and_then(|p| {
+            gio::File::for_path(p)
+                .query_info(
+                    "access::can-write",
+                    gio::FileQueryInfoFlags::NONE,
+                    gio::Cancellable::NONE,
+                )
+                .ok()
+        })
+        .map_or(false, |info| info.boolean("access::can-write"))
+}
+
+/// The part of a `[Context]` value that says what it is about, without the revoking `!` and
+/// the `:ro`, `:create` or `:rw` of filesystems, `home:ro` and `!home` are both about `home`.
+fn base_value(value: &str) -> &str {
+    let value = value.strip_prefix('!').unwrap_or(value);
+    value.split_once(':').map_or(value, |(base, _)| base)
+}
+
+fn load_keyfile(path: &PathBuf) -> Result<KeyFile> {
+    let keyfile = KeyFile::new();
+    if path.exists() {
+        keyfile.load_from_file(path, KeyFileFlags::KEEP_COMMENTS)?;
+    }
+    Ok(keyfile)
+}
+
+fn string_list(keyfile: &KeyFile, key: &str) -> Vec<String> {
+    keyfile
+        .string_list(CONTEXT, key)
+        .map(|values| values.iter().map(|v| v.to_string()).collect())
+        .unwrap_or_default()
+}
+
+pub fn load_overrides(app: &str, scope: &InstallationScope) -> Result<Overrides> {
+    let path = override_path(app, scope)?;
+    let keyfile = load_keyfile(&path)?;
+    let context = CONTEXT_KEYS
+        .iter()
+        .flat_map(|key| {
+            string_list(&keyfile, key)
+                .into_iter()
+                .map(move |value| (key.to_string(), value))
+        })
+        .collect();
+    let environment = keyfile
+        .keys(ENVIRONMENT)
+        .map(|keys| {
+            keys.iter()
+                .filter_map(|name| {
+                    let value = keyfile.string(ENVIRONMENT, name).ok()?;
+                    Some((name.to_string(), value.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(Overrides {
+        app: app.to_string(),
+        scope: scope.clone(),
+        context,
+        environment,
+        writable: is_writable(&path),
+    })
+}
+
+/// Writes `change` to the override file of `app` and returns the overrides it ends up with.
+/// The system installation usually needs root for this, see [`Overrides::writable`].
+pub fn apply_override(
+    app: &str,
+    scope: &InstallationScope,
+    change: &OverrideChange,
+) -> Result<Overrides> {
+    let path = override_path(app, scope)?;
+    if let OverrideChange::Reset = change {
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+    } else {
+        let keyfile = load_keyfile(&path)?;
+        edit_keyfile(&keyfile, change);
+        save_keyfile(&keyfile, &path)?;
+    }
+    println!("Applied override to {}: {:?}", app, change);
+    load_overrides(app, scope)
+}
+
+/// Applies a `[Context]` or `[Environment]` change to `keyfile`, leaving everything else in it
+/// alone. A context value replaces the others about the same thing, as `flatpak override` does.
+fn edit_keyfile(keyfile: &KeyFile, change: &OverrideChange) {
+    match change {
+        OverrideChange::Reset => {}
+        OverrideChange::Context { key, value, state } => {
+            let mut values: Vec<String> = string_list(keyfile, key)
+                .into_iter()
+                .filter(|v| base_value(v) != base_value(value))
+                .collect();
+            match state {
+                Some(OverrideState::Granted) => values.push(value.clone()),
+                Some(OverrideState::Revoked) => values.push(format!("!{}", base_value(value))),
+                None => {}
+            }
+            if values.is_empty() {
+                let _ = keyfile.remove_key(CONTEXT, key);
+            } else {
+                let values: Vec<&str> = values.iter().map(String::as_str).collect();
+                keyfile.set_string_list(CONTEXT, key, &values);
+            }
+        }
+        OverrideChange::Environment { name, value } => match value {
+            Some(value) => keyfile.set_string(ENVIRONMENT, name, value),
+            None => {
+                let _ = keyfile.remove_key(ENVIRONMENT, name);
+            }
+        },
+    }
+}
+
+fn save_keyfile(keyfile: &KeyFile, path: &PathBuf) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    Ok(keyfile.save_to_file(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OVERRIDE: &str = "# Written by hand
+[Context]
+filesystems=home:ro;xdg-download;
+shared=network;
+
+[Session Bus Policy]
+org.freedesktop.Notifications=talk
+";
+
+    fn keyfile(data: &str) -> KeyFile {
+        let keyfile = KeyFile::new();
+        keyfile
+            .load_from_data(data, KeyFileFlags::KEEP_COMMENTS)
+            .unwrap();
+        keyfile
+    }
+
+    fn context(key: &str, value: &str, state: Option<OverrideState>) -> OverrideChange {
+        OverrideChange::Context {
+            key: key.to_string(),
+            value: value.to_string(),
+            state,
+        }
+    }
+
+    fn environment(name: &str, value: Option<&str>) -> OverrideChange {
+        OverrideChange::Environment {
+            name: name.to_string(),
+            value: value.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn keeps_what_it_does_not_change() {
+        let path = std::env::temp_dir()
+            .join(format!("bazaar-overrides-{}", std::process::id()))
+            .join("org.foo.Bar");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, OVERRIDE).unwrap();
+
+        let keyfile = load_keyfile(&path).unwrap();
+        edit_keyfile(
+            &keyfile,
+            &context("sockets", "wayland", Some(OverrideState::Granted)),
+        );
+        save_keyfile(&keyfile, &path).unwrap();
+
+        let saved = load_keyfile(&path).unwrap();
+        assert_eq!(string_list(&saved, "sockets"), vec!["wayland"]);
+        assert_eq!(
+            string_list(&saved, "filesystems"),
+            vec!["home:ro", "xdg-download"]
+        );
+        assert_eq!(string_list(&saved, "shared"), vec!["network"]);
+        assert_eq!(
+            saved
+                .string("Session Bus Policy", "org.freedesktop.Notifications")
+                .unwrap(),
+            "talk"
+        );
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("# Written by hand"));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn grants_and_revokes_context_values() {
+        let keyfile = keyfile(OVERRIDE);
+        edit_keyfile(
+            &keyfile,
+            &context("shared", "ipc", Some(OverrideState::Revoked)),
+        );
+        assert_eq!(string_list(&keyfile, "shared"), vec!["network", "!ipc"]);
+        edit_keyfile(
+            &keyfile,
+            &context("shared", "ipc", Some(OverrideState::Granted)),
+        );
+        assert_eq!(string_list(&keyfile, "shared"), vec!["network", "ipc"]);
+        edit_keyfile(&keyfile, &context("shared", "network", None));
+        edit_keyfile(&keyfile, &context("shared", "ipc", None));
+        assert!(!keyfile.has_key(CONTEXT, "shared").unwrap_or(false));
+    }
+
+    #[test]
+    fn replaces_values_about_the_same_thing() {
+        let keyfile = keyfile(OVERRIDE);
+        edit_keyfile(
+            &keyfile,
+            &context("filesystems", "home", Some(OverrideState::Granted)),
+        );
+        assert_eq!(
+            string_list(&keyfile, "filesystems"),
+            vec!["xdg-download", "home"]
+        );
+        edit_keyfile(
+            &keyfile,
+            &context("filesystems", "home", Some(OverrideState::Revoked)),
+        );
+        assert_eq!(
+            string_list(&keyfile, "filesystems"),
+            vec!["xdg-download", "!home"]
+        );
+        edit_keyfile(
+            &keyfile,
+            &context(
+                "filesystems",
+                "xdg-download:ro",
+                Some(OverrideState::Granted),
+            ),
+        );
+        assert_eq!(
+            string_list(&keyfile, "filesystems"),
+            vec!["!home", "xdg-download:ro"]
+        );
+    }
+
+    #[test]
+    fn edits_environment_variables() {
+        let keyfile = keyfile(OVERRIDE);
+        edit_keyfile(&keyfile, &environment("GTK_THEME", Some("Adwaita:dark")));
+        edit_keyfile(&keyfile, &environment("LANG", Some("C")));
+        edit_keyfile(&keyfile, &environment("LANG", Some("de_DE.UTF-8")));
+        assert_eq!(
+            keyfile.string(ENVIRONMENT, "GTK_THEME").unwrap(),
+            "Adwaita:dark"
+        );
+        assert_eq!(keyfile.string(ENVIRONMENT, "LANG").unwrap(), "de_DE.UTF-8");
+        edit_keyfile(&keyfile, &environment("GTK_THEME", None));
+        assert!(keyfile.string(ENVIRONMENT, "GTK_THEME").is_err());
+        assert_eq!(
+            string_list(&keyfile, "filesystems"),
+            vec!["home:ro", "xdg-download"]
+        );
+    }
+
+    #[test]
+    fn reports_the_state_of_a_value() {
+        let overrides = Overrides {
+            context: vec![
+                ("sockets".into(), "wayland".into()),
+                ("sockets".into(), "!x11".into()),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            overrides.state("sockets", "wayland"),
+            Some(OverrideState::Granted)
+        );
+        assert_eq!(
+            overrides.state("sockets", "x11"),
+            Some(OverrideState::Revoked)
+        );
+        assert_eq!(overrides.state("sockets", "pulseaudio"), None);
+        assert_eq!(overrides.state("devices", "wayland"), None);
+    }
+}
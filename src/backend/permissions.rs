@@ -0,0 +1,412 @@
+use libflatpak::{
+    gio::Cancellable,
+    glib::{self, KeyFile, KeyFileFlags},
+    prelude::*,
+    traits::{InstallationExt, InstalledRefExt, RemoteRefExt},
+};
+
+use crate::{
+    backend::flatpak_backend::{
+        find_installed_ref, get_remote_ref_by_name, installations, InstallationScope,
+    },
+    error::{required, Result},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionGroup {
+    Filesystem,
+    Network,
+    Devices,
+    DBus,
+    Environment,
+    Other,
+}
+
+impl PermissionGroup {
+    pub const ALL: [PermissionGroup; 6] = [
+        PermissionGroup::Filesystem,
+        PermissionGroup::Network,
+        PermissionGroup::Devices,
+        PermissionGroup::DBus,
+        PermissionGroup::Environment,
+        PermissionGroup::Other,
+    ];
+
+    pub fn to_string(&self) -> String {
+        match self {
+            PermissionGroup::Filesystem => "Filesystem".into(),
+            PermissionGroup::Network => "Network".into(),
+            PermissionGroup::Devices => "Devices".into(),
+            PermissionGroup::DBus => "D-Bus".into(),
+            PermissionGroup::Environment => "Environment".into(),
+            PermissionGroup::Other => "Other".into(),
+        }
+    }
+}
+
+/// A single entry of the sandbox metadata, e.g. `filesystems=home` or `org.freedesktop.Notifications=talk`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Permission {
+    pub group: PermissionGroup,
+    /// The `[Context]` key or the section the entry comes from
+    pub key: String,
+    /// The entry as written in the metadata
+    pub value: String,
+    pub explanation: String,
+}
+
+/// What the sandbox of an app allows, parsed from the `[Context]`, `[Session Bus Policy]`,
+/// `[System Bus Policy]` and `[Environment]` sections of its metadata.
+#[derive(Debug, Clone, Default)]
+pub struct Permissions {
+    pub entries: Vec<Permission>,
+}
+
+impl Permissions {
+    pub fn parse(metadata: &glib::Bytes) -> Result<Self> {
+        let keyfile = KeyFile::new();
+        keyfile.load_from_bytes(metadata, KeyFileFlags::NONE)?;
+        let mut entries = vec![];
+        let list = |key: &str| -> Vec<String> {
+            keyfile
+                .string_list("Context", key)
+                .map(|values| values.iter().map(|v| v.to_string()).collect())
+                .unwrap_or_default()
+        };
+        for value in list("shared") {
+            entries.push(context_permission("shared", value));
+        }
+        for value in list("sockets") {
+            entries.push(context_permission("sockets", value));
+        }
+        for value in list("devices") {
+            entries.push(context_permission("devices", value));
+        }
+        for value in list("features") {
+            entries.push(context_permission("features", value));
+        }
+        for value in list("filesystems") {
+            entries.push(context_permission("filesystems", value));
+        }
+        for value in list("persistent") {
+            entries.push(Permission {
+                group: PermissionGroup::Filesystem,
+                key: "persistent".into(),
+                explanation: format!("Keeps ~/{} in its own data directory", value),
+                value,
+            });
+        }
+        for (section, bus) in [
+            ("Session Bus Policy", "session"),
+            ("System Bus Policy", "system"),
+        ] {
+            for (name, policy) in section_entries(&keyfile, section) {
+                entries.push(Permission {
+                    group: PermissionGroup::DBus,
+                    key: section.into(),
+                    explanation: bus_explanation(bus, &name, &policy),
+                    value: format!("{}={}", name, policy),
+                });
+            }
+        }
+        for (name, value) in section_entries(&keyfile, "Environment") {
+            entries.push(Permission {
+                group: PermissionGroup::Environment,
+                key: "Environment".into(),
+                explanation: format!("Runs with {} set to \"{}\"", name, value),
+                value: format!("{}={}", name, value),
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn group(&self, group: PermissionGroup) -> impl Iterator<Item = &Permission> {
+        self.entries.iter().filter(move |p| p.group == group)
+    }
+
+    /// Whether `value` of the `[Context]` key `key` is granted.
+    pub fn is_granted(&self, key: &str, value: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|p| p.key == key && p.value == value)
+    }
+}
+
+fn section_entries(keyfile: &KeyFile, section: &str) -> Vec<(String, String)> {
+    let Ok(keys) = keyfile.keys(section) else {
+        return vec![];
+    };
+    keys.iter()
+        .filter_map(|key| {
+            let value = keyfile.string(section, key).ok()?;
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Classifies and explains one value of a `[Context]` key. A leading `!` revokes the permission.
+fn context_permission(key: &str, value: String) -> Permission {
+    let (revoked, name) = match value.strip_prefix('!') {
+        Some(name) => (true, name),
+        None => (false, value.as_str()),
+    };
+    let (group, explanation) = match key {
+        "shared" => match name {
+            "network" => (PermissionGroup::Network, "Network access".to_string()),
+            "ipc" => (
+                PermissionGroup::Other,
+                "Shares memory with the host for X11".to_string(),
+            ),
+            other => (PermissionGroup::Other, format!("Shares {}", other)),
+        },
+        "sockets" => match name {
+            "x11" => (
+                PermissionGroup::Devices,
+                "Uses the X11 display, which can see other windows and keystrokes".to_string(),
+            ),
+            "fallback-x11" => (
+                PermissionGroup::Devices,
+                "Uses the X11 display when Wayland is not available".to_string(),
+            ),
+            "wayland" => (
+                PermissionGroup::Devices,
+                "Uses the Wayland display".to_string(),
+            ),
+            "pulseaudio" => (
+                PermissionGroup::Devices,
+                "Plays sound and may record it".to_string(),
+            ),
+            "session-bus" => (
+                PermissionGroup::DBus,
+                "Talks to every service on the session bus".to_string(),
+            ),
+            "system-bus" => (
+                PermissionGroup::DBus,
+                "Talks to every service on the system bus".to_string(),
+            ),
+            "ssh-auth" => (PermissionGroup::Other, "Uses your SSH keys".to_string()),
+            "gpg-agent" => (PermissionGroup::Other, "Uses your GPG keys".to_string()),
+            "pcsc" => (PermissionGroup::Devices, "Uses smart cards".to_string()),
+            "cups" => (PermissionGroup::Devices, "Prints".to_string()),
+            other => (PermissionGroup::Other, format!("Uses the {} socket", other)),
+        },
+        "devices" => (
+            PermissionGroup::Devices,
+            match name {
+                "dri" => "Uses the GPU for graphics acceleration".to_string(),
+                "kvm" => "Runs virtual machines".to_string(),
+                "shm" => "Shares memory in /dev/shm".to_string(),
+                "input" => "Reads input devices such as game controllers".to_string(),
+                "usb" => "Accesses USB devices".to_string(),
+                "all" => "Accesses all devices, including webcams".to_string(),
+                other => format!("Accesses {} devices", other),
+            },
+        ),
+        "features" => match name {
+            "bluetooth" => (PermissionGroup::Devices, "Uses Bluetooth".to_string()),
+            "canbus" => (PermissionGroup::Devices, "Uses the CAN bus".to_string()),
+            "devel" => (
+                PermissionGroup::Other,
+                "Can debug other processes with ptrace".to_string(),
+            ),
+            "multiarch" => (
+                PermissionGroup::Other,
+                "Runs programs built for other architectures".to_string(),
+            ),
+            other => (
+                PermissionGroup::Other,
+                format!("Uses the {} feature", other),
+            ),
+        },
+        _ => (PermissionGroup::Filesystem, filesystem_explanation(name)),
+    };
+    Permission {
+        group,
+        key: key.into(),
+        explanation: if revoked {
+            format!("Not allowed: {}", explanation)
+        } else {
+            explanation
+        },
+        value,
+    }
+}
+
+/// Explains a `filesystems` entry such as `xdg-download:ro` or `~/Games:create`.
+fn filesystem_explanation(value: &str) -> String {
+    let (path, mode) = value.rsplit_once(':').unwrap_or((value, "rw"));
+    let access = match mode {
+        "ro" => "Reads",
+        "create" => "Reads, writes and creates",
+        _ => "Reads and writes",
+    };
+    let location = match path {
+        "host" => "all your files and the system folders".to_string(),
+        "host-os" => "the system libraries and programs".to_string(),
+        "host-etc" => "the system configuration".to_string(),
+        "home" => "your home folder".to_string(),
+        xdg if xdg.starts_with("xdg-") => {
+            let (dir, sub) = xdg.split_once('/').unwrap_or((xdg, ""));
+            let dir = dir.trim_start_matches("xdg-");
+            if sub.is_empty() {
+                format!("your {} folder", dir)
+            } else {
+                format!("{} in your {} folder", sub, dir)
+            }
+        }
+        other => other.to_string(),
+    };
+    format!("{} {}", access, location)
+}
+
+fn bus_explanation(bus: &str, name: &str, policy: &str) -> String {
+    match policy {
+        "talk" => format!("Talks to {} on the {} bus", name, bus),
+        "own" => format!("Owns the name {} on the {} bus", name, bus),
+        "see" => format!("Can see {} on the {} bus", name, bus),
+        "none" => format!("Not allowed to reach {} on the {} bus", name, bus),
+        other => format!("{} access to {} on the {} bus", other, name, bus),
+    }
+}
+
+/// The permissions of `name`: those of the installed app if it is installed anywhere,
+/// otherwise those of the ref a plain install would pick.
+pub fn permissions_of(name: &str, cancellable: &Cancellable) -> Result<Permissions> {
+    for installation in installations()? {
+        let scope = InstallationScope::of(&installation);
+        if let Ok(installed) = find_installed_ref(name, &scope, cancellable) {
+            return Permissions::parse(&installed.load_metadata(Some(cancellable))?);
+        }
+    }
+    let (remote_ref, scope) = get_remote_ref_by_name(name, cancellable)?;
+    let metadata = match remote_ref.metadata() {
+        Some(metadata) => metadata,
+        None => scope.installation()?.fetch_remote_metadata_sync(
+            &required(remote_ref.remote_name(), "remote name")?,
+            &remote_ref,
+            Some(cancellable),
+        )?,
+    };
+    Permissions::parse(&metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METADATA: &str = "[Application]
+name=org.example.App
+runtime=org.gnome.Platform/x86_64/45
+
+[Context]
+shared=network;ipc;
+sockets=x11;wayland;!pulseaudio;
+devices=dri;
+features=devel;
+filesystems=xdg-download:ro;~/Games:create;home;xdg-config/app;
+persistent=.app;
+
+[Session Bus Policy]
+org.freedesktop.Notifications=talk
+
+[System Bus Policy]
+org.freedesktop.login1=see
+
+[Environment]
+GTK_THEME=Adwaita
+";
+
+    fn parse() -> Permissions {
+        Permissions::parse(&glib::Bytes::from_static(METADATA.as_bytes())).unwrap()
+    }
+
+    fn explanation(permissions: &Permissions, key: &str, value: &str) -> String {
+        permissions
+            .entries
+            .iter()
+            .find(|p| p.key == key && p.value == value)
+            .unwrap_or_else(|| panic!("no {}={}", key, value))
+            .explanation
+            .clone()
+    }
+
+    #[test]
+    fn parses_every_section() {
+        let permissions = parse();
+        assert_eq!(permissions.entries.len(), 15);
+        assert!(permissions.is_granted("shared", "network"));
+        assert!(permissions.is_granted("sockets", "!pulseaudio"));
+        assert!(!permissions.is_granted("sockets", "pulseaudio"));
+        assert_eq!(permissions.group(PermissionGroup::Network).count(), 1);
+        assert_eq!(permissions.group(PermissionGroup::DBus).count(), 2);
+        assert_eq!(permissions.group(PermissionGroup::Environment).count(), 1);
+        assert_eq!(permissions.group(PermissionGroup::Filesystem).count(), 5);
+    }
+
+    #[test]
+    fn explains_context_entries() {
+        let permissions = parse();
+        assert_eq!(
+            explanation(&permissions, "sockets", "!pulseaudio"),
+            "Not allowed: Plays sound and may record it"
+        );
+        assert_eq!(
+            explanation(&permissions, "devices", "dri"),
+            "Uses the GPU for graphics acceleration"
+        );
+        assert_eq!(
+            explanation(&permissions, "persistent", ".app"),
+            "Keeps ~/.app in its own data directory"
+        );
+    }
+
+    #[test]
+    fn explains_filesystems() {
+        assert_eq!(
+            filesystem_explanation("xdg-download:ro"),
+            "Reads your download folder"
+        );
+        assert_eq!(
+            filesystem_explanation("~/Games:create"),
+            "Reads, writes and creates ~/Games"
+        );
+        assert_eq!(
+            filesystem_explanation("home"),
+            "Reads and writes your home folder"
+        );
+        assert_eq!(
+            filesystem_explanation("xdg-config/app"),
+            "Reads and writes app in your config folder"
+        );
+    }
+
+    #[test]
+    fn explains_bus_policies() {
+        let permissions = parse();
+        assert_eq!(
+            explanation(
+                &permissions,
+                "Session Bus Policy",
+                "org.freedesktop.Notifications=talk"
+            ),
+            "Talks to org.freedesktop.Notifications on the session bus"
+        );
+        assert_eq!(
+            explanation(
+                &permissions,
+                "System Bus Policy",
+                "org.freedesktop.login1=see"
+            ),
+            "Can see org.freedesktop.login1 on the system bus"
+        );
+        assert_eq!(
+            explanation(&permissions, "Environment", "GTK_THEME=Adwaita"),
+            "Runs with GTK_THEME set to \"Adwaita\""
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_metadata() {
+        assert!(Permissions::parse(&glib::Bytes::from_static(b"not a keyfile")).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use std::path::PathBuf;
+
+use iced::futures::channel::mpsc;
+use libflatpak::{
+    gio::{self, traits::FileExt, Cancellable},
+    glib,
+    prelude::*,
+    traits::{InstallationExt, RemoteExt},
+    Installation, Remote,
+};
+
+use crate::{
+    backend::flatpak_backend::{self, installations, InstallationScope},
+    db::{self, Storage},
+    error::{required, BazaarError, Result},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteInfo {
+    pub name: String,
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub scope: InstallationScope,
+    pub disabled: bool,
+    pub noenumerate: bool,
+    pub priority: i32,
+}
+
+impl RemoteInfo {
+    fn new(remote: &Remote, scope: InstallationScope) -> Result<Self> {
+        Ok(Self {
+            name: required(remote.name(), "remote name")?,
+            title: remote.title().map(|t| t.to_string()),
+            url: remote.url().map(|u| u.to_string()),
+            scope,
+            disabled: remote.is_disabled(),
+            noenumerate: remote.is_noenumerate(),
+            priority: remote.prio(),
+        })
+    }
+
+    /// Whether its packages belong in the search index.
+    pub fn is_browsable(&self) -> bool {
+        !self.disabled && !self.noenumerate
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RemoteChange {
+    Remove,
+    SetDisabled(bool),
+    SetNoEnumerate(bool),
+    SetPriority(i32),
+}
+
+/// How a remote added from a bare repository URL checks the signatures of its commits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpgVerification {
+    /// The (binary, not armored) public key the repository is signed with
+    Key(PathBuf),
+    /// Accept unsigned commits, only after the user explicitly asked for it
+    Disabled,
+}
+
+pub fn list_remotes() -> Result<Vec<RemoteInfo>> {
+    let mut result = vec![];
+    for installation in installations()? {
+        let scope = InstallationScope::of(&installation);
+        for remote in installation.list_remotes(Cancellable::NONE)? {
+            match RemoteInfo::new(&remote, scope.clone()) {
+                Ok(info) => result.push(info),
+                Err(err) => eprintln!("Skipping remote: {}", err),
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Adds a remote from `location`, which is either the path or URL of a `.flatpakrepo` file, or
+/// the URL of the repository itself. A `.flatpakrepo` file brings its own GPG key, a bare
+/// repository URL needs `gpg` to say how its commits are verified.
+pub fn add_remote(
+    name: &str,
+    location: &str,
+    gpg: Option<&GpgVerification>,
+    scope: &InstallationScope,
+    cancellable: &Cancellable,
+) -> Result<RemoteInfo> {
+    let remote = if location.ends_with(".flatpakrepo") {
+        let file = if location.contains("://") {
+            gio::File::for_uri(location)
+        } else {
+            gio::File::for_path(location)
+        };
+        let (contents, _etag) = file.load_contents(Some(cancellable))?;
+        Remote::from_file(name, &glib::Bytes::from_owned(contents.to_vec()))?
+    } else if location.contains("://") {
+        let remote = Remote::new(name);
+        remote.set_url(location);
+        match gpg {
+            Some(GpgVerification::Key(path)) => {
+                let key = std::fs::read(path)?;
+                remote.set_gpg_key(&glib::Bytes::from_owned(key));
+                remote.set_gpg_verify(true);
+            }
+            Some(GpgVerification::Disabled) => {
+                println!("Adding remote {} without GPG verification", name);
+                remote.set_gpg_verify(false);
+            }
+            None => {
+                return Err(BazaarError::InvalidInput(format!(
+                    "the repository at {} needs a GPG key or explicitly disabled verification",
+                    location
+                )))
+            }
+        }
+        remote
+    } else {
+        return Err(BazaarError::InvalidInput(format!(
+            "{} is neither a .flatpakrepo file nor a repository URL",
+            location
+        )));
+    };
+    let installation = scope.installation()?;
+    installation.add_remote(&remote, false, Some(cancellable))?;
+    println!("Added remote {} from {}", name, location);
+    RemoteInfo::new(&remote, scope.clone())
+}
+
+pub fn modify_remote(
+    remote: &RemoteInfo,
+    change: &RemoteChange,
+    cancellable: &Cancellable,
+) -> Result<()> {
+    let installation = remote.scope.installation()?;
+    let flatpak_remote = installation.remote_by_name(&remote.name, Some(cancellable))?;
+    match change {
+        RemoteChange::Remove => {
+            installation.remove_remote(&remote.name, Some(cancellable))?;
+            println!("Removed remote {}", remote.name);
+            return Ok(());
+        }
+        RemoteChange::SetDisabled(disabled) => flatpak_remote.set_disabled(*disabled),
+        RemoteChange::SetNoEnumerate(noenumerate) => flatpak_remote.set_noenumerate(*noenumerate),
+        RemoteChange::SetPriority(priority) => flatpak_remote.set_prio(*priority),
+    }
+    installation.modify_remote(&flatpak_remote, Some(cancellable))?;
+    println!("Modified remote {}: {:?}", remote.name, change);
+    Ok(())
+}
+
+/// Downloads the appstream data of `remote` if its packages belong in the search index, which
+/// is the slow part of [`index_remote`] and needs no access to the database. Returns `None`
+/// for remotes that were removed or aren't browsable.
+pub fn fetch_remote(
+    remote: &str,
+    scope: &InstallationScope,
+    cancellable: &Cancellable,
+) -> Result<Option<Remote>> {
+    let installation = scope.installation()?;
+    let Ok(flatpak_remote) = installation.remote_by_name(remote, Some(cancellable)) else {
+        return Ok(None);
+    };
+    if !RemoteInfo::new(&flatpak_remote, scope.clone())?.is_browsable() {
+        return Ok(None);
+    }
+    installation.update_appstream_sync(remote, None, Some(cancellable))?;
+    Ok(Some(flatpak_remote))
+}
+
+/// Brings the packages of `remote` in `storage` in line with what [`fetch_remote`] returned:
+/// a fetched remote is re-indexed, the packages of any other are dropped.
+pub fn index_remote(
+    storage: &mut Storage,
+    remote: &str,
+    scope: &InstallationScope,
+    fetched: Option<&Remote>,
+) -> Result<()> {
+    // Nobody watches the index progress of a single remote
+    let (mut tx, _rx) = mpsc::channel::<db::Message>(1);
+    storage.delete_remote_packages(remote, scope)?;
+    if let Some(flatpak_remote) = fetched {
+        let installation: Installation = scope.installation()?;
+        flatpak_backend::load_remote_packages(storage, &installation, flatpak_remote, &mut tx)?;
+    }
+    storage.refresh_names()
+}
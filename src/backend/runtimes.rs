@@ -0,0 +1,290 @@
+use std::collections::{HashMap, HashSet};
+
+use iced::futures::channel::mpsc;
+use libflatpak::{
+    gio::Cancellable,
+    glib::{KeyFile, KeyFileFlags},
+    prelude::*,
+    traits::{InstallationExt, InstalledRefExt, RefExt},
+    InstalledRef, Transaction,
+};
+
+use crate::{
+    backend::flatpak_backend::{
+        installations, watch_transaction, InstallationScope, Operation, PackageId, PackageKind,
+        TransactionEvent,
+    },
+    error::{required, Result},
+};
+
+/// An installed runtime or extension and the apps that need it.
+#[derive(Debug, Clone)]
+pub struct RuntimeInfo {
+    pub name: String,
+    pub ref_: String,
+    pub branch: Option<String>,
+    pub kind: PackageKind,
+    pub scope: InstallationScope,
+    pub used_by: Vec<PackageId>,
+    /// Whether `flatpak uninstall --unused` would remove it
+    pub unused: bool,
+    /// In bytes
+    pub installed_size: u64,
+}
+
+/// The metadata of an installed ref, `None` if it can't be read.
+pub fn metadata_keyfile(pkg: &InstalledRef, cancellable: &Cancellable) -> Option<KeyFile> {
+    let metadata = pkg.load_metadata(Some(cancellable)).ok()?;
+    let keyfile = KeyFile::new();
+    keyfile
+        .load_from_bytes(&metadata, KeyFileFlags::NONE)
+        .ok()?;
+    Some(keyfile)
+}
+
+/// Reads `key` of `group` from the metadata of a ref.
+pub fn metadata_value(metadata: &KeyFile, group: &str, key: &str) -> Option<String> {
+    metadata.string(group, key).ok().map(|v| v.to_string())
+}
+
+/// What an installed ref depends on according to its metadata.
+#[derive(Debug, Clone, Default)]
+struct Dependencies {
+    /// The full ref, `app/org.foo.Bar/x86_64/stable`
+    ref_: String,
+    /// `[Application] runtime` of an app, as a full `runtime/...` ref. The `sdk` is only needed
+    /// to build the app, so it doesn't count.
+    runtime: Option<String>,
+    /// `[ExtensionOf] ref` of an extension
+    extension_of: Option<String>,
+}
+
+impl Dependencies {
+    fn read(pkg: &InstalledRef, cancellable: &Cancellable) -> Result<Self> {
+        let ref_ = required(pkg.format_ref(), "ref")?;
+        let Some(metadata) = metadata_keyfile(pkg, cancellable) else {
+            return Ok(Self {
+                ref_,
+                ..Default::default()
+            });
+        };
+        Ok(Self {
+            ref_,
+            runtime: metadata_value(&metadata, "Application", "runtime")
+                .map(|runtime| format!("runtime/{}", runtime)),
+            extension_of: metadata_value(&metadata, "ExtensionOf", "ref"),
+        })
+    }
+}
+
+/// How the runtimes and extensions among `refs` are used.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Usage {
+    used_by: Vec<PackageId>,
+    unused: bool,
+}
+
+/// The apps each runtime and extension of `refs` is used by, keyed by its full ref. An extension
+/// is used by the app it extends or by the apps of the runtime it extends. `unused` holds the
+/// refs flatpak itself would remove as unused.
+fn usage(refs: &[Dependencies], unused: &HashSet<String>) -> HashMap<String, Usage> {
+    // Full runtime ref -> the apps running on it
+    let mut dependents: HashMap<&str, Vec<PackageId>> = HashMap::new();
+    for deps in refs.iter().filter(|deps| deps.ref_.starts_with("app/")) {
+        if let (Some(runtime), Some(app)) = (&deps.runtime, deps.ref_.split('/').nth(1)) {
+            dependents
+                .entry(runtime.as_str())
+                .or_default()
+                .push(app.to_string());
+        }
+    }
+    refs.iter()
+        .filter(|deps| !deps.ref_.starts_with("app/"))
+        .map(|deps| {
+            let used_by = match &deps.extension_of {
+                Some(parent) if parent.starts_with("app/") => parent
+                    .split('/')
+                    .nth(1)
+                    .map(|app| vec![app.to_string()])
+                    .unwrap_or_default(),
+                Some(parent) => dependents.get(parent.as_str()).cloned().unwrap_or_default(),
+                None => dependents
+                    .get(deps.ref_.as_str())
+                    .cloned()
+                    .unwrap_or_default(),
+            };
+            let usage = Usage {
+                used_by,
+                unused: unused.contains(&deps.ref_),
+            };
+            (deps.ref_.clone(), usage)
+        })
+        .collect()
+}
+
+pub fn list_runtimes(cancellable: &Cancellable) -> Result<Vec<RuntimeInfo>> {
+    println!("Getting installed runtimes");
+    let mut result = vec![];
+    for installation in installations()? {
+        let scope = InstallationScope::of(&installation);
+        let refs = installation.list_installed_refs(Some(cancellable))?;
+        let unused: HashSet<String> = installation
+            .list_unused_refs(None, Some(cancellable))?
+            .iter()
+            .filter_map(|pkg| pkg.format_ref().map(|r| r.to_string()))
+            .collect();
+        let dependencies = refs
+            .iter()
+            .map(|pkg| Dependencies::read(pkg, cancellable))
+            .collect::<Result<Vec<_>>>()?;
+        let mut usage = usage(&dependencies, &unused);
+
+        for (pkg, deps) in refs.iter().zip(&dependencies) {
+            let Some(usage) = usage.remove(&deps.ref_) else {
+                continue;
+            };
+            result.push(RuntimeInfo {
+                name: required(pkg.name(), "name")?,
+                branch: pkg.branch().map(|b| b.to_string()),
+                kind: if deps.extension_of.is_some() {
+                    PackageKind::Extension
+                } else {
+                    PackageKind::Runtime
+                },
+                scope: scope.clone(),
+                used_by: usage.used_by,
+                unused: usage.unused,
+                installed_size: pkg.installed_size(),
+                ref_: deps.ref_.clone(),
+            });
+        }
+    }
+    Ok(result)
+}
+
+/// The equivalent of `flatpak uninstall --unused` for every installation, returns the removed refs.
+pub fn remove_unused(
+    operation: &Operation,
+    tx: mpsc::Sender<TransactionEvent>,
+) -> Result<Vec<String>> {
+    let mut removed = vec![];
+    for installation in installations()? {
+        let refs: Vec<String> = installation
+            .list_unused_refs(None, Some(&operation.cancellable))?
+            .iter()
+            .filter_map(|pkg| pkg.format_ref().map(|r| r.to_string()))
+            .collect();
+        if refs.is_empty() {
+            continue;
+        }
+        let t = Transaction::for_installation(&installation, Some(&operation.cancellable))?;
+        watch_transaction(&t, operation.id, &tx);
+        for ref_ in &refs {
+            t.add_uninstall(ref_)?;
+        }
+        println!("Added the transaction: remove unused {:?}", refs);
+        let res = t.run(Some(&operation.cancellable));
+        println!("Finished the transaction: {:?}", res);
+        res?;
+        removed.extend(refs);
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(name: &str, runtime: &str) -> Dependencies {
+        Dependencies {
+            ref_: format!("app/{}/x86_64/stable", name),
+            runtime: Some(format!("runtime/{}", runtime)),
+            extension_of: None,
+        }
+    }
+
+    fn runtime(ref_: &str, extension_of: Option<&str>) -> Dependencies {
+        Dependencies {
+            ref_: format!("runtime/{}", ref_),
+            runtime: None,
+            extension_of: extension_of.map(str::to_owned),
+        }
+    }
+
+    fn refs() -> Vec<Dependencies> {
+        vec![
+            app("org.gnome.Maps", "org.gnome.Platform/x86_64/45"),
+            app("org.gnome.Weather", "org.gnome.Platform/x86_64/45"),
+            app("org.kde.Kate", "org.kde.Platform/x86_64/5.15-23.08"),
+            runtime("org.gnome.Platform/x86_64/45", None),
+            runtime("org.gnome.Sdk/x86_64/45", None),
+            runtime("org.kde.Platform/x86_64/5.15-23.08", None),
+            runtime(
+                "org.gnome.Platform.Locale/x86_64/45",
+                Some("runtime/org.gnome.Platform/x86_64/45"),
+            ),
+            runtime(
+                "org.kde.Kate.Locale/x86_64/stable",
+                Some("app/org.kde.Kate/x86_64/stable"),
+            ),
+            runtime("org.freedesktop.Platform/x86_64/22.08", None),
+        ]
+    }
+
+    fn used_by(usage: &HashMap<String, Usage>, ref_: &str) -> Vec<PackageId> {
+        usage[&format!("runtime/{}", ref_)].used_by.clone()
+    }
+
+    #[test]
+    fn counts_the_apps_running_on_a_runtime() {
+        let usage = usage(&refs(), &HashSet::new());
+        assert_eq!(
+            used_by(&usage, "org.gnome.Platform/x86_64/45"),
+            vec!["org.gnome.Maps", "org.gnome.Weather"]
+        );
+        assert_eq!(
+            used_by(&usage, "org.kde.Platform/x86_64/5.15-23.08"),
+            vec!["org.kde.Kate"]
+        );
+        assert!(used_by(&usage, "org.freedesktop.Platform/x86_64/22.08").is_empty());
+        assert!(!usage.contains_key("app/org.gnome.Maps/x86_64/stable"));
+    }
+
+    #[test]
+    fn does_not_count_the_sdk() {
+        let mut refs = refs();
+        // Apps name the Sdk they were built with, nothing needs it to run them
+        refs.push(Dependencies {
+            ref_: "app/org.gnome.Builder/x86_64/stable".into(),
+            runtime: Some("runtime/org.gnome.Platform/x86_64/45".into()),
+            extension_of: None,
+        });
+        let usage = usage(&refs, &HashSet::new());
+        assert!(used_by(&usage, "org.gnome.Sdk/x86_64/45").is_empty());
+    }
+
+    #[test]
+    fn counts_the_users_of_the_extended_ref() {
+        let usage = usage(&refs(), &HashSet::new());
+        assert_eq!(
+            used_by(&usage, "org.gnome.Platform.Locale/x86_64/45"),
+            vec!["org.gnome.Maps", "org.gnome.Weather"]
+        );
+        assert_eq!(
+            used_by(&usage, "org.kde.Kate.Locale/x86_64/stable"),
+            vec!["org.kde.Kate"]
+        );
+    }
+
+    #[test]
+    fn marks_what_flatpak_would_remove() {
+        let unused = HashSet::from([
+            "runtime/org.gnome.Sdk/x86_64/45".to_string(),
+            "runtime/org.freedesktop.Platform/x86_64/22.08".to_string(),
+        ]);
+        let usage = usage(&refs(), &unused);
+        assert!(usage["runtime/org.gnome.Sdk/x86_64/45"].unused);
+        assert!(usage["runtime/org.freedesktop.Platform/x86_64/22.08"].unused);
+        assert!(!usage["runtime/org.gnome.Platform/x86_64/45"].unused);
+    }
+}
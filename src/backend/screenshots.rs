@@ -0,0 +1,212 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use libflatpak::{
+    gio::{self, prelude::FileExtManual, Cancellable},
+    glib::{self, ChecksumType},
+};
+
+use crate::error::Result;
+
+/// Upper bound of the cache directory before the least recently used screenshots go.
+pub const DEFAULT_CACHE_SIZE: u64 = 200 * 1024 * 1024;
+
+/// Where screenshots come from. The cache only ever asks for URLs it does not hold yet.
+pub trait Fetcher: Send + Sync {
+    fn fetch(&self, url: &str, cancellable: &Cancellable) -> Result<Vec<u8>>;
+}
+
+/// Downloads through gio, which handles `http(s)://` as well as `file://` URLs.
+#[derive(Debug, Default, Clone)]
+pub struct GioFetcher;
+
+impl Fetcher for GioFetcher {
+    fn fetch(&self, url: &str, cancellable: &Cancellable) -> Result<Vec<u8>> {
+        let (contents, _etag) = gio::File::for_uri(url).load_contents(Some(cancellable))?;
+        Ok(contents.to_vec())
+    }
+}
+
+/// Screenshots on disk, one file per URL named after its SHA-256. Reading a screenshot marks it
+/// as recently used, once the directory grows past `max_bytes` the oldest ones are evicted.
+#[derive(Clone)]
+pub struct ScreenshotCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    fetcher: Arc<dyn Fetcher>,
+}
+
+impl ScreenshotCache {
+    /// A cache in `$XDG_CACHE_HOME/bazaar/screenshots`.
+    pub fn new(fetcher: Arc<dyn Fetcher>, max_bytes: u64) -> Self {
+        Self::in_dir(
+            glib::user_cache_dir().join("bazaar").join("screenshots"),
+            fetcher,
+            max_bytes,
+        )
+    }
+
+    pub fn in_dir(dir: PathBuf, fetcher: Arc<dyn Fetcher>, max_bytes: u64) -> Self {
+        Self {
+            dir,
+            max_bytes,
+            fetcher,
+        }
+    }
+
+    fn path_of(&self, url: &str) -> PathBuf {
+        let key = glib::compute_checksum_for_string(ChecksumType::Sha256, url)
+            .map(|sum| sum.to_string())
+            .unwrap_or_else(|| url.replace(['/', ':'], "_"));
+        self.dir.join(key)
+    }
+
+    /// The cached file of `url`, fetched first if it is not cached yet.
+    pub fn get(&self, url: &str, cancellable: &Cancellable) -> Result<PathBuf> {
+        let path = self.path_of(url);
+        if path.exists() {
+            // Touching the file keeps it from being evicted next
+            std::fs::File::options()
+                .write(true)
+                .open(&path)?
+                .set_modified(SystemTime::now())?;
+            return Ok(path);
+        }
+        let contents = self.fetcher.fetch(url, cancellable)?;
+        std::fs::create_dir_all(&self.dir)?;
+        // Written aside and moved in place so that a half written file is never shown
+        let partial = path.with_extension("part");
+        std::fs::write(&partial, &contents)?;
+        std::fs::rename(&partial, &path)?;
+        println!("Cached screenshot {}", url);
+        self.evict(&path)?;
+        Ok(path)
+    }
+
+    /// Removes the least recently used screenshots until the cache fits `max_bytes`, `keep`
+    /// stays even if it alone is larger.
+    fn evict(&self, keep: &Path) -> Result<()> {
+        let mut files = vec![];
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_file() && entry.path().extension().is_none() {
+                files.push((metadata.modified()?, metadata.len(), entry.path()));
+            }
+        }
+        let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+        files.sort();
+        for (_, len, path) in files {
+            if total <= self.max_bytes {
+                break;
+            }
+            if path == keep {
+                continue;
+            }
+            // Another thread may have evicted it already
+            if std::fs::remove_file(&path).is_ok() {
+                println!("Evicted screenshot {}", path.display());
+                total -= len;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::*;
+    use crate::error::BazaarError;
+
+    /// Serves the last path segment of every URL from a fixture directory, counting the fetches.
+    struct DirFetcher {
+        root: PathBuf,
+        fetches: AtomicUsize,
+    }
+
+    impl Fetcher for DirFetcher {
+        fn fetch(&self, url: &str, _cancellable: &Cancellable) -> Result<Vec<u8>> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            let name = url
+                .rsplit('/')
+                .next()
+                .filter(|name| !name.is_empty())
+                .ok_or(BazaarError::NotFound(url.to_string()))?;
+            Ok(std::fs::read(self.root.join(name))?)
+        }
+    }
+
+    /// A cache over fixtures `a.png`, `b.png` and `c.png` of 4 bytes each, in fresh directories.
+    fn cache(test: &str, max_bytes: u64) -> (ScreenshotCache, Arc<DirFetcher>) {
+        let root = std::env::temp_dir().join(format!("bazaar-{}-{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let fixtures = root.join("fixtures");
+        std::fs::create_dir_all(&fixtures).unwrap();
+        for name in ["a", "b", "c"] {
+            std::fs::write(fixtures.join(format!("{}.png", name)), name.repeat(4)).unwrap();
+        }
+        let fetcher = Arc::new(DirFetcher {
+            root: fixtures,
+            fetches: AtomicUsize::new(0),
+        });
+        let cache = ScreenshotCache::in_dir(root.join("cache"), fetcher.clone(), max_bytes);
+        (cache, fetcher)
+    }
+
+    fn url(name: &str) -> String {
+        format!("https://example.org/shots/{}.png", name)
+    }
+
+    #[test]
+    fn fetches_a_miss_once_and_serves_hits_from_disk() {
+        let (cache, fetcher) = cache("screenshot-hit", DEFAULT_CACHE_SIZE);
+        let cancellable = Cancellable::new();
+        let path = cache.get(&url("a"), &cancellable).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"aaaa");
+        assert_eq!(cache.get(&url("a"), &cancellable).unwrap(), path);
+        assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn names_files_after_the_sha256_of_the_url() {
+        let (cache, _) = cache("screenshot-name", DEFAULT_CACHE_SIZE);
+        let path = cache.get(&url("a"), &Cancellable::new()).unwrap();
+        assert_eq!(
+            path.file_name().unwrap(),
+            "2441853b99973ae333237a6333099c6b537cbd00a6e7e1d236512dc17143d271"
+        );
+    }
+
+    #[test]
+    fn failed_fetches_leave_nothing_behind() {
+        let (cache, _) = cache("screenshot-miss", DEFAULT_CACHE_SIZE);
+        assert!(cache.get(&url("missing"), &Cancellable::new()).is_err());
+        assert!(!cache.path_of(&url("missing")).exists());
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_past_max_bytes() {
+        let (cache, fetcher) = cache("screenshot-evict", 10);
+        let cancellable = Cancellable::new();
+        let a = cache.get(&url("a"), &cancellable).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        let b = cache.get(&url("b"), &cancellable).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        // Reading `a` again makes `b` the least recently used
+        cache.get(&url("a"), &cancellable).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        let c = cache.get(&url("c"), &cancellable).unwrap();
+        assert!(a.exists());
+        assert!(!b.exists());
+        assert!(c.exists());
+        assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,567 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use iced::futures::channel::mpsc;
+use libflatpak::{
+    gio::Cancellable,
+    prelude::*,
+    traits::{InstallationExt, InstalledRefExt, RefExt, RemoteExt},
+    RefKind, Transaction,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::{
+        flatpak_backend::{
+            pending_updates, watch_transaction, InstallationScope, Operation, TransactionEvent,
+        },
+        manifest::{ManifestApp, ManifestFormat},
+        remotes::{self, GpgVerification},
+    },
+    error::{required, BazaarError, Result},
+};
+
+/// A remote the machine must have.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DesiredRemote {
+    pub name: String,
+    /// A `.flatpakrepo` file, relative to the state file, or the URL of the repository
+    pub location: String,
+    /// `user`, `system` or the id of a custom installation
+    pub installation: String,
+    /// The GPG key a repository URL is verified with, relative to the state file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpg_key: Option<PathBuf>,
+    /// Adds a repository URL without verifying its commits, instead of a `gpg_key`
+    #[serde(default)]
+    pub skip_gpg_verify: bool,
+}
+
+impl DesiredRemote {
+    pub fn scope(&self) -> InstallationScope {
+        InstallationScope::from_id(&self.installation)
+    }
+
+    pub fn gpg_verification(&self) -> Option<GpgVerification> {
+        match (&self.gpg_key, self.skip_gpg_verify) {
+            (Some(key), _) => Some(GpgVerification::Key(key.clone())),
+            (None, true) => Some(GpgVerification::Disabled),
+            (None, false) => None,
+        }
+    }
+}
+
+/// A checked-in description of the remotes and apps a machine should have. The apps are
+/// written like the entries of an exported manifest, so an export works as a state file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DesiredState {
+    #[serde(default)]
+    pub remotes: Vec<DesiredRemote>,
+    #[serde(default)]
+    pub apps: Vec<ManifestApp>,
+    /// Uninstall the apps that are not listed, only in the installations the state mentions
+    #[serde(default)]
+    pub remove_unlisted: bool,
+}
+
+impl DesiredState {
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let mut state: Self = match ManifestFormat::of(path)? {
+            ManifestFormat::Toml => toml::from_str(&contents)?,
+            ManifestFormat::Json => serde_json::from_str(&contents)?,
+        };
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for remote in state.remotes.iter_mut() {
+            if let Some(key) = remote.gpg_key.as_mut() {
+                *key = dir.join(&*key);
+            }
+            if !remote.location.contains("://") {
+                remote.location = dir.join(&remote.location).to_string_lossy().to_string();
+            }
+        }
+        Ok(state)
+    }
+
+    /// Every installation a remote or app of the state goes to.
+    fn scopes(&self) -> Vec<InstallationScope> {
+        let mut scopes = vec![];
+        for scope in self
+            .remotes
+            .iter()
+            .map(DesiredRemote::scope)
+            .chain(self.apps.iter().map(ManifestApp::scope))
+        {
+            if !scopes.contains(&scope) {
+                scopes.push(scope);
+            }
+        }
+        scopes
+    }
+}
+
+/// One change that brings the machine closer to the state file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncStep {
+    AddRemote(DesiredRemote),
+    Install(ManifestApp),
+    /// To the latest commit, or to the pinned one if the app has one
+    Update(ManifestApp),
+    Remove((String, InstallationScope)),
+}
+
+impl SyncStep {
+    pub fn scope(&self) -> InstallationScope {
+        match self {
+            SyncStep::AddRemote(remote) => remote.scope(),
+            SyncStep::Install(app) | SyncStep::Update(app) => app.scope(),
+            SyncStep::Remove((_, scope)) => scope.clone(),
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        match self {
+            SyncStep::AddRemote(remote) => {
+                format!("Add remote {} from {}", remote.name, remote.location)
+            }
+            SyncStep::Install(app) => format!("Install {} from {}", app.ref_, app.remote),
+            SyncStep::Update(app) => match &app.commit {
+                Some(commit) => format!("Move {} to commit {}", app.ref_, commit),
+                None => format!("Update {}", app.ref_),
+            },
+            SyncStep::Remove((ref_, _)) => format!("Remove {}", ref_),
+        }
+    }
+}
+
+/// The steps that converge the machine on a state file, in the order they are applied.
+#[derive(Debug, Clone, Default)]
+pub struct SyncPlan {
+    pub path: PathBuf,
+    pub steps: Vec<SyncStep>,
+    /// Installations the state file mentions that do not exist here, left alone
+    pub unavailable: Vec<InstallationScope>,
+}
+
+impl SyncPlan {
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// How many installed apps the plan uninstalls.
+    pub fn removals(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|step| matches!(step, SyncStep::Remove(_)))
+            .count()
+    }
+}
+
+/// What an installation has, as far as a sync is concerned.
+#[derive(Debug, Clone, Default)]
+pub struct InstallationState {
+    pub remotes: HashSet<String>,
+    /// Full ref of each installed app -> its installed commit
+    pub installed: HashMap<String, Option<String>>,
+    /// Refs with an update pending
+    pub updatable: HashSet<String>,
+}
+
+/// The steps that bring the installation `scope` from `current` to what `state` lists for it.
+fn diff(
+    state: &DesiredState,
+    scope: &InstallationScope,
+    current: &InstallationState,
+) -> Vec<SyncStep> {
+    let mut steps = vec![];
+    for remote in state.remotes.iter().filter(|r| r.scope() == *scope) {
+        if !current.remotes.contains(&remote.name) {
+            steps.push(SyncStep::AddRemote(remote.clone()));
+        }
+    }
+
+    let apps: Vec<&ManifestApp> = state.apps.iter().filter(|a| a.scope() == *scope).collect();
+    for app in apps.iter() {
+        let step = match (current.installed.get(&app.ref_), &app.commit) {
+            (None, _) => Some(SyncStep::Install((*app).clone())),
+            (Some(installed), Some(pinned)) if installed.as_ref() != Some(pinned) => {
+                Some(SyncStep::Update((*app).clone()))
+            }
+            (Some(_), None) if current.updatable.contains(&app.ref_) => {
+                Some(SyncStep::Update((*app).clone()))
+            }
+            _ => None,
+        };
+        steps.extend(step);
+    }
+    // An installation the state only lists remotes for keeps its apps
+    if state.remove_unlisted && !apps.is_empty() {
+        let listed: HashSet<&str> = apps.iter().map(|app| app.ref_.as_str()).collect();
+        let mut unlisted: Vec<&String> = current
+            .installed
+            .keys()
+            .filter(|ref_| !listed.contains(ref_.as_str()))
+            .collect();
+        unlisted.sort();
+        for ref_ in unlisted {
+            steps.push(SyncStep::Remove((ref_.clone(), scope.clone())));
+        }
+    }
+    steps
+}
+
+/// Diffs the state file at `path` against the remotes and installed refs of its installations.
+pub fn plan_sync(path: &Path, cancellable: &Cancellable) -> Result<SyncPlan> {
+    let state = DesiredState::read(path)?;
+    let updatable: HashMap<InstallationScope, HashSet<String>> = pending_updates(cancellable)?
+        .into_iter()
+        .map(|(scope, refs)| (scope, refs.into_iter().collect()))
+        .collect();
+    let mut plan = SyncPlan {
+        path: path.to_path_buf(),
+        ..Default::default()
+    };
+    for scope in state.scopes() {
+        // E.g. a custom installation of another machine, the rest of the state still applies
+        let installation = match scope.installation() {
+            Ok(installation) => installation,
+            Err(err) => {
+                eprintln!("Skipping installation {}: {}", scope.to_string(), err);
+                plan.unavailable.push(scope);
+                continue;
+            }
+        };
+        let mut current = InstallationState {
+            updatable: updatable.get(&scope).cloned().unwrap_or_default(),
+            ..Default::default()
+        };
+        current.remotes = installation
+            .list_remotes(Some(cancellable))?
+            .iter()
+            .filter_map(|remote| remote.name().map(|n| n.to_string()))
+            .collect();
+        for pkg in installation.list_installed_refs_by_kind(RefKind::App, Some(cancellable))? {
+            current.installed.insert(
+                required(pkg.format_ref(), "ref")?,
+                pkg.commit().map(|c| c.to_string()),
+            );
+        }
+        plan.steps.extend(diff(&state, &scope, &current));
+    }
+    println!(
+        "Sync plan for {}: {} steps",
+        path.display(),
+        plan.steps.len()
+    );
+    Ok(plan)
+}
+
+/// Applies `plan`: the remotes are added first, then each installation gets a single
+/// transaction for its installs, updates and removals. Returns what was done.
+pub fn apply_sync(
+    plan: &SyncPlan,
+    operation: &Operation,
+    tx: mpsc::Sender<TransactionEvent>,
+) -> Result<Vec<String>> {
+    let mut done = vec![];
+    for step in plan.steps.iter() {
+        if let SyncStep::AddRemote(remote) = step {
+            remotes::add_remote(
+                &remote.name,
+                &remote.location,
+                remote.gpg_verification().as_ref(),
+                &remote.scope(),
+                &operation.cancellable,
+            )?;
+            done.push(step.to_string());
+        }
+    }
+
+    let mut scopes: Vec<InstallationScope> = vec![];
+    for scope in plan.steps.iter().map(SyncStep::scope) {
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+    for scope in scopes {
+        let steps: Vec<&SyncStep> = plan
+            .steps
+            .iter()
+            .filter(|step| step.scope() == scope && !matches!(step, SyncStep::AddRemote(_)))
+            .collect();
+        if steps.is_empty() {
+            continue;
+        }
+        let installation = scope.installation()?;
+        let t = Transaction::for_installation(&installation, Some(&operation.cancellable))?;
+        watch_transaction(&t, operation.id, &tx);
+        for step in steps.iter() {
+            match step {
+                SyncStep::Install(app) => t.add_install(&app.remote, &app.ref_, &[])?,
+                SyncStep::Update(app) => t.add_update(&app.ref_, &[], app.commit.as_deref())?,
+                SyncStep::Remove((ref_, _)) => t.add_uninstall(ref_)?,
+                SyncStep::AddRemote(_) => (),
+            }
+        }
+        println!(
+            "Added the transaction: sync {} steps in {}",
+            steps.len(),
+            scope.to_string()
+        );
+        let res = t.run(Some(&operation.cancellable));
+        println!("Finished the transaction: {:?}", res);
+        res?;
+        done.extend(steps.iter().map(|step| step.to_string()));
+
+        // A fresh install lands on the latest commit, pinned apps are moved afterwards
+        let pinned: Vec<&ManifestApp> = steps
+            .iter()
+            .filter_map(|step| match step {
+                SyncStep::Install(app) if app.commit.is_some() => Some(app),
+                _ => None,
+            })
+            .collect();
+        if pinned.is_empty() {
+            continue;
+        }
+        let t = Transaction::for_installation(&installation, Some(&operation.cancellable))?;
+        watch_transaction(&t, operation.id, &tx);
+        for app in pinned {
+            t.add_update(&app.ref_, &[], app.commit.as_deref())?;
+        }
+        println!("Added the transaction: move pinned apps to their commits");
+        let res = t.run(Some(&operation.cancellable));
+        println!("Finished the transaction: {:?}", res);
+        res?;
+    }
+    Ok(done)
+}
+
+/// Plans and, unless `dry_run` is set, applies the state file at `path` without the UI. A plan
+/// that uninstalls apps is only applied with `allow_removals`, a script that worked yesterday
+/// should not start removing apps because someone set `remove_unlisted`.
+/// Returns whether the machine is in sync afterwards.
+pub fn sync_headless(path: &Path, dry_run: bool, allow_removals: bool) -> Result<bool> {
+    let operation = Operation::new(0);
+    let plan = plan_sync(path, &operation.cancellable)?;
+    for scope in plan.unavailable.iter() {
+        eprintln!(
+            "Warning: the installation {} does not exist here, its entries are skipped",
+            scope.to_string()
+        );
+    }
+    if plan.is_empty() {
+        println!("{} is in sync", path.display());
+        return Ok(true);
+    }
+    for step in plan.steps.iter() {
+        println!("  {} ({})", step.to_string(), step.scope().to_string());
+    }
+    let removals = plan.removals();
+    if removals > 0 {
+        eprintln!();
+        eprintln!(
+            "WARNING: remove_unlisted is set, {} installed apps not listed in {} {} UNINSTALLED",
+            removals,
+            path.display(),
+            if dry_run || !allow_removals {
+                "would be"
+            } else {
+                "will be"
+            }
+        );
+        eprintln!();
+    }
+    if dry_run {
+        return Ok(false);
+    }
+    if removals > 0 && !allow_removals {
+        return Err(BazaarError::InvalidInput(format!(
+            "{} would uninstall apps, pass --allow-removals to apply it",
+            path.display()
+        )));
+    }
+    // Nobody follows the progress, the transactions log their operations anyway
+    let (tx, _rx) = mpsc::channel(64);
+    let done = apply_sync(&plan, &operation, tx)?;
+    println!("Applied {} steps", done.len());
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(name: &str, installation: &str, commit: Option<&str>) -> ManifestApp {
+        ManifestApp {
+            ref_: format!("app/{}/x86_64/stable", name),
+            remote: "flathub".into(),
+            branch: "stable".into(),
+            installation: installation.into(),
+            commit: commit.map(str::to_owned),
+        }
+    }
+
+    fn remote(name: &str, installation: &str) -> DesiredRemote {
+        DesiredRemote {
+            name: name.into(),
+            location: format!("https://example.org/{}.flatpakrepo", name),
+            installation: installation.into(),
+            gpg_key: None,
+            skip_gpg_verify: false,
+        }
+    }
+
+    /// An installation with `installed` apps at commit `c1` and the `flathub` remote.
+    fn current(installed: &[&str], updatable: &[&str]) -> InstallationState {
+        InstallationState {
+            remotes: HashSet::from(["flathub".to_string()]),
+            installed: installed
+                .iter()
+                .map(|name| (app(name, "user", None).ref_, Some("c1".to_string())))
+                .collect(),
+            updatable: updatable
+                .iter()
+                .map(|name| app(name, "user", None).ref_)
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn adds_missing_remotes_of_the_installation() {
+        let state = DesiredState {
+            remotes: vec![
+                remote("flathub", "user"),
+                remote("gnome-nightly", "user"),
+                remote("kde", "system"),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            diff(&state, &InstallationScope::User, &current(&[], &[])),
+            [SyncStep::AddRemote(remote("gnome-nightly", "user"))]
+        );
+    }
+
+    #[test]
+    fn installs_and_updates_listed_apps() {
+        let state = DesiredState {
+            apps: vec![
+                app("org.a.Missing", "user", None),
+                app("org.b.Updatable", "user", None),
+                app("org.c.Current", "user", None),
+                app("org.d.Pinned", "user", Some("c0")),
+                app("org.e.AtPin", "user", Some("c1")),
+                app("org.f.Elsewhere", "system", None),
+            ],
+            ..Default::default()
+        };
+        let current = current(
+            &[
+                "org.b.Updatable",
+                "org.c.Current",
+                "org.d.Pinned",
+                "org.e.AtPin",
+            ],
+            &["org.b.Updatable", "org.e.AtPin"],
+        );
+        assert_eq!(
+            diff(&state, &InstallationScope::User, &current),
+            [
+                SyncStep::Install(app("org.a.Missing", "user", None)),
+                SyncStep::Update(app("org.b.Updatable", "user", None)),
+                SyncStep::Update(app("org.d.Pinned", "user", Some("c0"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn removes_unlisted_apps_only_when_asked() {
+        let mut state = DesiredState {
+            apps: vec![app("org.a.Kept", "user", None)],
+            ..Default::default()
+        };
+        let current = current(&["org.a.Kept", "org.c.Extra", "org.b.Extra"], &[]);
+        assert!(diff(&state, &InstallationScope::User, &current).is_empty());
+
+        state.remove_unlisted = true;
+        assert_eq!(
+            diff(&state, &InstallationScope::User, &current),
+            [
+                SyncStep::Remove((
+                    "app/org.b.Extra/x86_64/stable".into(),
+                    InstallationScope::User
+                )),
+                SyncStep::Remove((
+                    "app/org.c.Extra/x86_64/stable".into(),
+                    InstallationScope::User
+                )),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_installations_without_app_entries_alone() {
+        let state = DesiredState {
+            remotes: vec![remote("flathub", "user")],
+            apps: vec![app("org.a.Kept", "system", None)],
+            remove_unlisted: true,
+        };
+        let current = current(&["org.b.Extra"], &[]);
+        assert!(diff(&state, &InstallationScope::User, &current).is_empty());
+    }
+
+    #[test]
+    fn picks_the_gpg_verification_of_a_remote() {
+        let mut desired = remote("repo", "user");
+        assert_eq!(desired.gpg_verification(), None);
+        desired.skip_gpg_verify = true;
+        assert_eq!(desired.gpg_verification(), Some(GpgVerification::Disabled));
+        desired.gpg_key = Some("repo.gpg".into());
+        assert_eq!(
+            desired.gpg_verification(),
+            Some(GpgVerification::Key("repo.gpg".into()))
+        );
+    }
+
+    #[test]
+    fn reads_gpg_keys_relative_to_the_state_file() {
+        let dir = std::env::temp_dir().join(format!("bazaar-sync-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.toml");
+        std::fs::write(
+            &path,
+            r#"
+[[remotes]]
+name = "repo"
+location = "https://example.org/repo"
+installation = "user"
+gpg_key = "keys/repo.gpg"
+
+[[remotes]]
+name = "local"
+location = "repos/local.flatpakrepo"
+installation = "user"
+
+[[remotes]]
+name = "shared"
+location = "/etc/flatpak/shared.flatpakrepo"
+installation = "system"
+"#,
+        )
+        .unwrap();
+        let state = DesiredState::read(&path).unwrap();
+        assert_eq!(state.remotes[0].gpg_key, Some(dir.join("keys/repo.gpg")));
+        assert_eq!(state.remotes[0].location, "https://example.org/repo");
+        assert_eq!(
+            state.remotes[1].location,
+            dir.join("repos/local.flatpakrepo").to_string_lossy()
+        );
+        assert_eq!(state.remotes[1].gpg_key, None);
+        assert_eq!(state.remotes[2].location, "/etc/flatpak/shared.flatpakrepo");
+        assert!(!state.remove_unlisted);
+        assert!(state.apps.is_empty());
+    }
+}
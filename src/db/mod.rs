@@ -9,10 +9,25 @@ use iced_futures::subscription::Recipe;
 use rusqlite::Connection;
 
 use crate::{
-    backend::{self, flatpak_backend::Package},
+    backend::{
+        self,
+        details::PackageDetails,
+        flatpak_backend::{InstallationScope, Package},
+    },
     db,
+    error::{BazaarError, Result},
 };
 
+/// Tables holding the appstream details of a row in `packages`, keyed by its `id`
+const DETAIL_TABLES: [&str; 6] = [
+    "package_details",
+    "categories",
+    "keywords",
+    "content_ratings",
+    "screenshots",
+    "releases",
+];
+
 #[derive(Debug)]
 pub struct Storage {
     pub conn: Connection,
@@ -27,7 +42,7 @@ pub struct Person {
 }
 
 impl Storage {
-    pub fn new() -> rusqlite::Result<Self> {
+    pub fn new() -> Result<Self> {
         let path = PathBuf::from("./apps.db3");
         let conn = Connection::open(path)?;
         Ok(Self {
@@ -35,7 +50,8 @@ impl Storage {
             all_packages: None,
         })
     }
-    pub fn create_table(&mut self) -> rusqlite::Result<usize> {
+    pub fn create_table(&mut self) -> Result<usize> {
+        self.create_detail_tables()?;
         Ok(self.conn.execute(
             "CREATE TABLE if not exists packages (
                 id          INTEGER PRIMARY KEY,
@@ -44,56 +60,168 @@ impl Storage {
                 summary     TEXT,
                 iconpath    TEXT,
                 desc        TEXT,
-                kind        TEXT
+                kind        TEXT,
+                remote      TEXT,
+                branch      TEXT,
+                download_size   INTEGER,
+                installed_size  INTEGER,
+                scope       TEXT
                 )",
             (),
         )?)
     }
 
-    pub fn insert(&self, package: &Package) -> rusqlite::Result<usize> {
+    fn create_detail_tables(&self) -> Result<()> {
+        Ok(self.conn.execute_batch(
+            "CREATE TABLE if not exists package_details (
+                package_id  INTEGER PRIMARY KEY REFERENCES packages(id),
+                developer   TEXT,
+                license     TEXT,
+                homepage    TEXT,
+                bugtracker  TEXT,
+                donation    TEXT
+                );
+            CREATE TABLE if not exists categories (
+                package_id  INTEGER NOT NULL REFERENCES packages(id),
+                category    TEXT NOT NULL
+                );
+            CREATE INDEX if not exists categories_category ON categories (category);
+            CREATE TABLE if not exists keywords (
+                package_id  INTEGER NOT NULL REFERENCES packages(id),
+                keyword     TEXT NOT NULL
+                );
+            CREATE TABLE if not exists content_ratings (
+                package_id  INTEGER NOT NULL REFERENCES packages(id),
+                attribute   TEXT NOT NULL,
+                value       TEXT NOT NULL
+                );
+            CREATE TABLE if not exists screenshots (
+                package_id  INTEGER NOT NULL REFERENCES packages(id),
+                position    INTEGER NOT NULL,
+                caption     TEXT,
+                url         TEXT NOT NULL,
+                thumbnail   TEXT,
+                is_default  INTEGER NOT NULL
+                );
+            CREATE TABLE if not exists releases (
+                package_id  INTEGER NOT NULL REFERENCES packages(id),
+                position    INTEGER NOT NULL,
+                version     TEXT NOT NULL,
+                date        TEXT,
+                description TEXT
+                );",
+        )?)
+    }
+
+    /// Brings a database created by an older version up to the current schema. Packages indexed
+    /// before the detail tables existed get their details on the next refresh of their remote.
+    pub fn migrate(&self) -> Result<()> {
+        self.ensure_column("packages", "remote", "TEXT")?;
+        self.ensure_column("packages", "branch", "TEXT")?;
+        self.ensure_column("packages", "download_size", "INTEGER")?;
+        self.ensure_column("packages", "installed_size", "INTEGER")?;
+        self.ensure_column("packages", "scope", "TEXT")?;
+        self.create_detail_tables()
+    }
+
+    fn ensure_column(&self, table: &str, column: &str, decl: &str) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT name FROM pragma_table_info('{}')", table))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<usize, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .iter()
+            .any(|name| name == column);
+        if !exists {
+            self.conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl),
+                (),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Drops every package indexed from `remote` of the installation `scope`, e.g. after it was
+    /// removed or disabled. Rows indexed before the scope was stored go as well.
+    pub fn delete_remote_packages(&self, remote: &str, scope: &InstallationScope) -> Result<usize> {
+        const MATCHES: &str = "remote = ?1 AND (scope = ?2 OR scope IS NULL)";
+        for table in DETAIL_TABLES {
+            self.conn.execute(
+                &format!(
+                    "DELETE FROM {} WHERE package_id IN (SELECT id FROM packages WHERE {})",
+                    table, MATCHES
+                ),
+                (remote, scope.id()),
+            )?;
+        }
         Ok(self.conn.execute(
-            "INSERT INTO packages (name, prettyname, summary, iconpath, desc, kind) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            &format!("DELETE FROM packages WHERE {}", MATCHES),
+            (remote, scope.id()),
+        )?)
+    }
+
+    /// Re-reads the package names used by the fuzzy search.
+    pub fn refresh_names(&mut self) -> Result<()> {
+        self.all_packages = Some(self.all_names()?);
+        Ok(())
+    }
+
+    pub fn insert(&self, package: &Package) -> Result<usize> {
+        Ok(self.conn.execute(
+            "INSERT INTO packages (name, prettyname, summary, iconpath, desc, kind, remote, branch, download_size, installed_size, scope) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
             (
                 &package.name,
                 &package.pretty_name,
                 &package.summary,
                 &package
                     .icon_path
-                    .clone()
-                    .map(|p| p.to_str().unwrap().to_string()),
+                    .as_ref()
+                    .and_then(|p| p.to_str().map(str::to_string)),
                 &package.description,
                 &package.kind.to_string(),
+                &package.remote,
+                &package.branch,
+                package.download_size.map(|size| size as i64),
+                package.installed_size.map(|size| size as i64),
+                package.scope.id(),
             ),
         )?)
     }
-    pub fn insert_batch(&self, packages: &Vec<Package>) -> rusqlite::Result<()> {
-        self.conn.execute("begin", ())?;
-        for pkg in packages {
-            self.conn.execute(
-                "INSERT INTO packages (name, prettyname, summary, iconpath, desc, kind) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    /// Inserts all `packages` in one transaction, which is rolled back if any of them fails.
+    pub fn insert_batch(&mut self, packages: &[(Package, PackageDetails)]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for (pkg, details) in packages {
+            tx.execute(
+                "INSERT INTO packages (name, prettyname, summary, iconpath, desc, kind, remote, branch, download_size, installed_size, scope) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
                 (
                     &pkg.name,
                     &pkg.pretty_name,
                     &pkg.summary,
                     &pkg.icon_path
-                        .clone()
-                        .map(|p| p.to_str().unwrap().to_string()),
+                        .as_ref()
+                        .and_then(|p| p.to_str().map(str::to_string)),
                     &pkg.description,
                     &pkg.kind.to_string(),
+                    &pkg.remote,
+                    &pkg.branch,
+                    pkg.download_size.map(|size| size as i64),
+                    pkg.installed_size.map(|size| size as i64),
+                    pkg.scope.id(),
                 ),
             )?;
+            insert_details(&tx, tx.last_insert_rowid(), details)?;
         }
-        self.conn.execute("end", ())?;
+        tx.commit()?;
         Ok(())
     }
 
-    pub fn all_names(&self) -> rusqlite::Result<Vec<String>> {
+    pub fn all_names(&self) -> Result<Vec<String>> {
         let mut stmt = self.conn.prepare("SELECT name FROM packages")?;
 
         let pkgs = stmt
             .query_map([], |row| Ok(row.get::<usize, String>(0)?))?
-            .map(|s| s.unwrap())
-            .collect::<Vec<_>>();
+            .collect::<rusqlite::Result<Vec<_>>>()?;
         Ok(pkgs)
     }
 }
@@ -103,12 +231,94 @@ pub enum Action {
     Load(mpsc::Sender<Message>),
 }
 
+/// Inserts the appstream details of the package row `package_id`.
+fn insert_details(conn: &Connection, package_id: i64, details: &PackageDetails) -> Result<()> {
+    conn.execute(
+        "INSERT INTO package_details (package_id, developer, license, homepage, bugtracker, donation) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        (
+            package_id,
+            &details.developer,
+            &details.license,
+            &details.homepage,
+            &details.bugtracker,
+            &details.donation,
+        ),
+    )?;
+    for category in &details.categories {
+        conn.execute(
+            "INSERT INTO categories (package_id, category) VALUES (?1, ?2)",
+            (package_id, category),
+        )?;
+    }
+    for keyword in &details.keywords {
+        conn.execute(
+            "INSERT INTO keywords (package_id, keyword) VALUES (?1, ?2)",
+            (package_id, keyword),
+        )?;
+    }
+    for (attribute, value) in &details.content_rating {
+        conn.execute(
+            "INSERT INTO content_ratings (package_id, attribute, value) VALUES (?1, ?2, ?3)",
+            (package_id, attribute, value),
+        )?;
+    }
+    for (position, screenshot) in details.screenshots.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO screenshots (package_id, position, caption, url, thumbnail, is_default) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            (
+                package_id,
+                position,
+                &screenshot.caption,
+                &screenshot.url,
+                &screenshot.thumbnail,
+                screenshot.is_default,
+            ),
+        )?;
+    }
+    for (position, release) in details.releases.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO releases (package_id, position, version, date, description) VALUES (?1, ?2, ?3, ?4, ?5)",
+            (
+                package_id,
+                position,
+                &release.version,
+                &release.date,
+                &release.description,
+            ),
+        )?;
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub enum Message {
     Ready(mpsc::Sender<Action>),
     Progress(u32),
     Loaded(Arc<Mutex<db::Storage>>),
+    Error(BazaarError),
 }
+
+fn load(tx: mpsc::Sender<Message>) -> Result<Storage> {
+    let dbpath = PathBuf::from("./apps.db3");
+    if dbpath.exists() {
+        println!("DB exists, returning");
+        let mut d = db::Storage::new()?;
+        d.migrate()?;
+        // d.create_table()?;
+        // backend::flatpak_backend::get_packages_remote(&mut d, tx)?;
+        d.all_packages = Some(d.all_names()?);
+        Ok(d)
+    } else {
+        println!("DB doesn't exist, creating");
+        let mut d = db::Storage::new()?;
+        d.create_table()?;
+        backend::flatpak_backend::get_packages_remote(&mut d, tx)?;
+        d.all_packages = Some(d.all_names()?);
+        // let _ = tx.try_send(Message::Progress(50));
+        Ok(d)
+    }
+}
+
 pub fn subscribe() -> iced::Subscription<Message> {
     iced::Subscription::from_recipe(DBSubscription)
 }
@@ -131,25 +341,10 @@ impl Recipe for DBSubscription {
         let (tx, rx) = mpsc::channel(10);
         futures::stream::once(async { Message::Ready(tx) })
             .chain(rx.map(|action| match action {
-                Action::Load(mut tx) => {
-                    let dbpath = PathBuf::from("./apps.db3");
-                    if dbpath.exists() {
-                        println!("DB exists, returning");
-                        let mut d = db::Storage::new().unwrap();
-                        // d.create_table().unwrap();
-                        // backend::flatpak_backend::get_packages_remote(&d, tx);
-                        d.all_packages = Some(d.all_names().unwrap());
-                        Message::Loaded(Arc::new(Mutex::new(d)))
-                    } else {
-                        println!("DB doesn't exist, creating");
-                        let mut d = db::Storage::new().unwrap();
-                        d.create_table().unwrap();
-                        backend::flatpak_backend::get_packages_remote(&d, tx);
-                        d.all_packages = Some(d.all_names().unwrap());
-                        // let _ = tx.try_send(Message::Progress(50));
-                        Message::Loaded(Arc::new(Mutex::new(d)))
-                    }
-                }
+                Action::Load(tx) => match load(tx) {
+                    Ok(d) => Message::Loaded(Arc::new(Mutex::new(d))),
+                    Err(err) => Message::Error(err),
+                },
             }))
             .boxed()
     }
@@ -4,56 +4,106 @@ use std::{
 };
 
 use iced::futures::{self, channel::mpsc};
+use rusqlite::{types::Type, OptionalExtension, Row};
 use rust_fuzzy_search::fuzzy_search_best_n;
 
-use crate::backend::flatpak_backend::{Package, PackageKind};
+use crate::{
+    backend::{
+        details::{PackageDetails, Release, Screenshot},
+        flatpak_backend::{InstallationScope, Package, PackageKind},
+    },
+    error::Result,
+};
 
 use super::Storage;
 
-pub fn search(db: Arc<Mutex<Storage>>, st: &str) -> Vec<Package> {
+fn package_from_row(row: &Row) -> rusqlite::Result<Package> {
+    let kind = PackageKind::try_from(row.get::<usize, String>(5)?)
+        .map_err(|err| rusqlite::Error::FromSqlConversionFailure(5, Type::Text, Box::new(err)))?;
+    let name: String = row.get(0)?;
+    let pretty_name: Option<String> = row.get(1)?;
+    let summary: Option<String> = row.get(2)?;
+    let icon_path: Option<PathBuf> = row
+        .get::<usize, Option<String>>(3)?
+        .map(|s| PathBuf::from(s));
+    let description: Option<String> = row.get(4)?;
+    let remote: Option<String> = row.get(6)?;
+    let branch: Option<String> = row.get(7)?;
+    let download_size: Option<i64> = row.get(8)?;
+    let installed_size: Option<i64> = row.get(9)?;
+    // Unset for rows indexed before the scope was stored
+    let scope: Option<String> = row.get(10)?;
+    Ok(
+        Package::new(name, pretty_name, description, summary, icon_path, kind)
+            .with_scope(scope.map_or(Default::default(), |id| InstallationScope::from_id(&id)))
+            .with_remote(remote)
+            .with_branch(branch)
+            .with_sizes(
+                download_size.map(|size| size as u64),
+                installed_size.map(|size| size as u64),
+            ),
+    )
+}
+
+fn apps_by_name<'a>(
+    db: &Storage,
+    names: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<Package>> {
+    let mut final_result = vec![];
+    let mut stmt = db.conn.prepare(
+        "SELECT name, prettyname, summary, iconpath, desc, kind, remote, branch, download_size, installed_size, scope FROM packages WHERE name = :name",
+    )?;
+    for name in names {
+        for package in stmt.query_map(&[(":name", name)], package_from_row)? {
+            let package = package?;
+            // A remote configured in several installations is indexed once per installation
+            if package.kind == PackageKind::App
+                && !final_result
+                    .iter()
+                    .any(|p: &Package| p.name == package.name)
+            {
+                final_result.push(package);
+            }
+        }
+    }
+    Ok(final_result)
+}
+
+/// The package `name`, from `remote` of the installation `scope` if given.
+pub fn package_by_name(
+    db: Arc<Mutex<Storage>>,
+    name: &str,
+    remote: Option<&str>,
+    scope: Option<&InstallationScope>,
+) -> Result<Option<Package>> {
+    let db = db.lock().unwrap();
+    Ok(db
+        .conn
+        .query_row(
+            "SELECT name, prettyname, summary, iconpath, desc, kind, remote, branch,
+                    download_size, installed_size, scope
+             FROM packages
+             WHERE name = ?1 AND (?2 IS NULL OR remote = ?2) AND (?3 IS NULL OR scope = ?3)
+             LIMIT 1",
+            (name, remote, scope.map(InstallationScope::id)),
+            package_from_row,
+        )
+        .optional()?)
+}
+
+pub fn search(db: Arc<Mutex<Storage>>, st: &str) -> Result<Vec<Package>> {
+    let db = db.lock().unwrap();
     let mut res = vec![];
-    db.lock().unwrap().all_packages.as_ref().map(|pkgs| {
+    db.all_packages.as_ref().map(|pkgs| {
         let found = fuzzy_search_best_n(st, &pkgs, 10);
         found
             .iter()
             .for_each(|(name, _score)| res.push(name.to_string()));
     });
-    let mut final_result = vec![];
-    if let Ok(mut stmt) = db.lock().unwrap().conn.prepare(
-        "SELECT name, prettyname, summary, iconpath, desc, kind FROM packages WHERE name = :name",
-    ) {
-        for name in res {
-            let _ = stmt
-                .query_map(&[(":name", name.as_str())], |row| {
-                    let kind = PackageKind::from(row.get::<usize, String>(5).unwrap());
-                    if kind == PackageKind::App {
-                        let name: String = row.get(0).unwrap();
-                        let pretty_name: Option<String> = row.get(1).unwrap();
-                        let summary: Option<String> = row.get(2).unwrap();
-                        let icon_path: Option<PathBuf> = row
-                            .get::<usize, Option<String>>(3)
-                            .unwrap()
-                            .map(|s| PathBuf::from(s));
-                        let description: Option<String> = row.get(4).unwrap();
-                        final_result.push(Package::new(
-                            name,
-                            pretty_name,
-                            description,
-                            summary,
-                            icon_path,
-                            kind,
-                        ));
-                    }
-                    Ok(())
-                })
-                .unwrap()
-                .collect::<Vec<_>>();
-        }
-    }
-    return final_result;
+    apps_by_name(&db, res.iter().map(String::as_str))
 }
 
-pub fn get_staff_picks(db: Arc<Mutex<Storage>>) -> Vec<Package> {
+pub fn get_staff_picks(db: Arc<Mutex<Storage>>) -> Result<Vec<Package>> {
     let staff_picks_names = vec![
         "org.blender.Blender",
         "com.logseq.Logseq",
@@ -61,37 +111,116 @@ pub fn get_staff_picks(db: Arc<Mutex<Storage>>) -> Vec<Package> {
         "im.riot.Riot",
         "com.github.wwmm.easyeffects",
     ];
-    let mut final_result = vec![];
-    if let Ok(mut stmt) = db.lock().unwrap().conn.prepare(
-        "SELECT name, prettyname, summary, iconpath, desc, kind FROM packages WHERE name = :name",
-    ) {
-        for name in staff_picks_names {
-            let _ = stmt
-                .query_map(&[(":name", name)], |row| {
-                    let kind = PackageKind::from(row.get::<usize, String>(5).unwrap());
-                    if kind == PackageKind::App {
-                        let name: String = row.get(0).unwrap();
-                        let pretty_name: Option<String> = row.get(1).unwrap();
-                        let summary: Option<String> = row.get(2).unwrap();
-                        let icon_path: Option<PathBuf> = row
-                            .get::<usize, Option<String>>(3)
-                            .unwrap()
-                            .map(|s| PathBuf::from(s));
-                        let description: Option<String> = row.get(4).unwrap();
-                        final_result.push(Package::new(
-                            name,
-                            pretty_name,
-                            description,
-                            summary,
-                            icon_path,
-                            kind,
-                        ));
-                    }
-                    Ok(())
-                })
-                .unwrap()
-                .collect::<Vec<_>>();
-        }
-    }
-    return final_result;
+    apps_by_name(&db.lock().unwrap(), staff_picks_names)
+}
+
+/// Apps listing `category` in their appstream data, e.g. `Graphics` or `Game`, by name.
+pub fn apps_in_category(db: Arc<Mutex<Storage>>, category: &str) -> Result<Vec<Package>> {
+    let db = db.lock().unwrap();
+    let mut stmt = db.conn.prepare(
+        "SELECT p.name FROM packages p JOIN categories c ON c.package_id = p.id
+         WHERE c.category = ?1 AND p.kind = 'App'
+         GROUP BY p.name
+         ORDER BY MIN(COALESCE(p.prettyname, p.name)) COLLATE NOCASE",
+    )?;
+    let names = stmt
+        .query_map([category], |row| row.get::<usize, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    apps_by_name(&db, names.iter().map(String::as_str))
+}
+
+fn column_of<T: rusqlite::types::FromSql>(
+    db: &Storage,
+    sql: &str,
+    package_id: i64,
+) -> Result<Vec<T>> {
+    let mut stmt = db.conn.prepare(sql)?;
+    let values = stmt
+        .query_map((package_id,), |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<T>>>()?;
+    Ok(values)
+}
+
+/// The appstream details of `name`, from `remote` of the installation `scope` if given. `None`
+/// if the package is unknown or was indexed before details were stored.
+pub fn package_details(
+    db: Arc<Mutex<Storage>>,
+    name: &str,
+    remote: Option<&str>,
+    scope: Option<&InstallationScope>,
+) -> Result<Option<PackageDetails>> {
+    let db = db.lock().unwrap();
+    let row = db
+        .conn
+        .query_row(
+            "SELECT d.package_id, d.developer, d.license, d.homepage, d.bugtracker, d.donation
+             FROM package_details d JOIN packages p ON p.id = d.package_id
+             WHERE p.name = ?1 AND (?2 IS NULL OR p.remote = ?2)
+                   AND (?3 IS NULL OR p.scope = ?3)
+             LIMIT 1",
+            (name, remote, scope.map(InstallationScope::id)),
+            |row| {
+                Ok((
+                    row.get::<usize, i64>(0)?,
+                    PackageDetails {
+                        developer: row.get(1)?,
+                        license: row.get(2)?,
+                        homepage: row.get(3)?,
+                        bugtracker: row.get(4)?,
+                        donation: row.get(5)?,
+                        ..Default::default()
+                    },
+                ))
+            },
+        )
+        .optional()?;
+    let Some((id, mut details)) = row else {
+        return Ok(None);
+    };
+    details.categories = column_of(
+        &db,
+        "SELECT category FROM categories WHERE package_id = ?1",
+        id,
+    )?;
+    details.keywords = column_of(
+        &db,
+        "SELECT keyword FROM keywords WHERE package_id = ?1",
+        id,
+    )?;
+
+    let mut stmt = db
+        .conn
+        .prepare("SELECT attribute, value FROM content_ratings WHERE package_id = ?1")?;
+    details.content_rating = stmt
+        .query_map((id,), |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut stmt = db.conn.prepare(
+        "SELECT caption, url, thumbnail, is_default FROM screenshots
+         WHERE package_id = ?1 ORDER BY position",
+    )?;
+    details.screenshots = stmt
+        .query_map((id,), |row| {
+            Ok(Screenshot {
+                caption: row.get(0)?,
+                url: row.get(1)?,
+                thumbnail: row.get(2)?,
+                is_default: row.get(3)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut stmt = db.conn.prepare(
+        "SELECT version, date, description FROM releases WHERE package_id = ?1 ORDER BY position",
+    )?;
+    details.releases = stmt
+        .query_map((id,), |row| {
+            Ok(Release {
+                version: row.get(0)?,
+                date: row.get(1)?,
+                description: row.get(2)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(Some(details))
 }
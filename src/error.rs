@@ -0,0 +1,100 @@
+use std::fmt;
+
+use libflatpak::glib;
+
+/// Everything that can go wrong while talking to flatpak or the package database.
+#[derive(Debug, Clone)]
+pub enum BazaarError {
+    Flatpak(glib::Error),
+    Database(String),
+    Io(String),
+    /// A ref or remote is missing a property flatpak normally always sets
+    MissingField(&'static str),
+    UnknownPackageKind(String),
+    NotFound(String),
+    /// The app has to be closed first, e.g. before its data is restored
+    AppRunning(String),
+    /// An app manifest that could not be read or written
+    Manifest(String),
+    /// Something the user entered that can't be used as is
+    InvalidInput(String),
+}
+
+pub type Result<T> = std::result::Result<T, BazaarError>;
+
+impl BazaarError {
+    pub fn title(&self) -> &'static str {
+        match self {
+            BazaarError::Flatpak(_) => "Flatpak error",
+            BazaarError::Database(_) => "Database error",
+            BazaarError::Io(_) => "File error",
+            BazaarError::MissingField(_) => "Invalid package",
+            BazaarError::UnknownPackageKind(_) => "Invalid package",
+            BazaarError::NotFound(_) => "Not found",
+            BazaarError::AppRunning(_) => "App is running",
+            BazaarError::Manifest(_) => "Invalid manifest",
+            BazaarError::InvalidInput(_) => "Invalid input",
+        }
+    }
+}
+
+impl fmt::Display for BazaarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BazaarError::Flatpak(err) => write!(f, "{}", err),
+            BazaarError::Database(err) => write!(f, "{}", err),
+            BazaarError::Io(err) => write!(f, "{}", err),
+            BazaarError::MissingField(field) => write!(f, "missing {}", field),
+            BazaarError::UnknownPackageKind(kind) => write!(f, "unknown package kind {:?}", kind),
+            BazaarError::NotFound(what) => write!(f, "{} not found", what),
+            BazaarError::AppRunning(app) => write!(f, "{} is running, close it first", app),
+            BazaarError::Manifest(err) => write!(f, "{}", err),
+            BazaarError::InvalidInput(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for BazaarError {}
+
+impl From<glib::Error> for BazaarError {
+    fn from(err: glib::Error) -> Self {
+        BazaarError::Flatpak(err)
+    }
+}
+
+impl From<rusqlite::Error> for BazaarError {
+    fn from(err: rusqlite::Error) -> Self {
+        BazaarError::Database(err.to_string())
+    }
+}
+
+impl From<std::io::Error> for BazaarError {
+    fn from(err: std::io::Error) -> Self {
+        BazaarError::Io(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for BazaarError {
+    fn from(err: serde_json::Error) -> Self {
+        BazaarError::Manifest(err.to_string())
+    }
+}
+
+impl From<toml::de::Error> for BazaarError {
+    fn from(err: toml::de::Error) -> Self {
+        BazaarError::Manifest(err.to_string())
+    }
+}
+
+impl From<toml::ser::Error> for BazaarError {
+    fn from(err: toml::ser::Error) -> Self {
+        BazaarError::Manifest(err.to_string())
+    }
+}
+
+/// Turns a property flatpak returned as `None` into a [`BazaarError::MissingField`].
+pub fn required<T: ToString>(value: Option<T>, field: &'static str) -> Result<String> {
+    value
+        .map(|v| v.to_string())
+        .ok_or(BazaarError::MissingField(field))
+}
@@ -1,9 +1,65 @@
-use std::path::PathBuf;
+use std::{ffi::OsStr, path::PathBuf};
 
 pub(crate) mod backend;
 pub(crate) mod db;
+pub(crate) mod error;
 pub(crate) mod ui;
 
+const USAGE: &str = "Usage: bazaar [<.flatpakref or .flatpak file>]
+       bazaar --sync <state file> [--dry-run] [--allow-removals]";
+
+/// Prints the usage and exits with `code`, on stderr unless it is a success.
+fn usage(code: i32) -> ! {
+    if code == 0 {
+        println!("{}", USAGE);
+    } else {
+        eprintln!("{}", USAGE);
+    }
+    std::process::exit(code)
+}
+
 fn main() -> iced::Result {
-    ui::main_window::run()
+    let mut args = std::env::args_os().skip(1);
+    let first = args.next();
+    match first.as_deref().and_then(OsStr::to_str) {
+        Some("--help") | Some("-h") => usage(0),
+        // `--sync <state file> [--dry-run] [--allow-removals]` converges the machine on the state
+        // file without the UI, uninstalling apps only with `--allow-removals`
+        Some("--sync") => {
+            let Some(path) = args.next().map(PathBuf::from) else {
+                usage(2);
+            };
+            let (mut dry_run, mut allow_removals) = (false, false);
+            for arg in args {
+                if arg == "--dry-run" {
+                    dry_run = true;
+                } else if arg == "--allow-removals" {
+                    allow_removals = true;
+                } else {
+                    eprintln!("Unknown option {}", arg.to_string_lossy());
+                    usage(2);
+                }
+            }
+            match backend::sync::sync_headless(&path, dry_run, allow_removals) {
+                Ok(true) => std::process::exit(0),
+                // A dry run found changes to make
+                Ok(false) => std::process::exit(1),
+                Err(err) => {
+                    eprintln!("{}: {}", err.title(), err);
+                    std::process::exit(2);
+                }
+            }
+        }
+        Some(option) if option.starts_with('-') => {
+            eprintln!("Unknown option {}", option);
+            usage(2);
+        }
+        _ => (),
+    }
+    if args.next().is_some() {
+        usage(2);
+    }
+    // A .flatpakref or .flatpak file to install, e.g. when opened from a file manager
+    let file = first.map(PathBuf::from);
+    ui::main_window::run(file)
 }
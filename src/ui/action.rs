@@ -1,41 +1,202 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
 use iced::futures;
 use iced::futures::channel::mpsc;
 use iced_futures::core::Hasher;
+use libflatpak::gio::Cancellable;
 
 use crate::{
-    backend::flatpak_backend::{self, Package, PackageId},
+    backend::{
+        app_data::{self, OrphanedData},
+        backups::{self, Backup},
+        details::{releases_since, PackageDetails, Release},
+        eol::{self, EolNotice},
+        flatpak_backend::{
+            self, InstallationScope, Operation, OperationId, Package, PackageId, RefOrigin,
+            TransactionEvent,
+        },
+        history::{self, Commit},
+        local_packages::{self, LocalPackage},
+        manifest::{self, ImportPlan},
+        masks,
+        overrides::{self, OverrideChange, Overrides},
+        permissions::{self, Permissions},
+        remotes::{self, GpgVerification, RemoteChange, RemoteInfo},
+        runtimes::{self, RuntimeInfo},
+        screenshots::ScreenshotCache,
+        sync::{self, SyncPlan},
+    },
     db::{
-        search::{get_staff_picks, search},
+        search::{apps_in_category, get_staff_picks, package_by_name, package_details, search},
         Storage,
     },
+    error::BazaarError,
 };
 
 #[derive(Debug, Clone)]
 pub enum Action {
     RefreshInstalled,
     RefreshUpdates,
-    Uninstall(PackageId),
+    /// Looks for installed apps and runtimes their remotes marked end-of-life
+    CheckEol,
+    /// Replaces an end-of-life app with the app it was renamed to
+    MigrateEol(EolNotice),
+    Install(PackageId),
+    /// Reads a `.flatpakref` or `.flatpak` file for the confirmation page
+    OpenLocal(PathBuf),
+    InstallLocal(LocalPackage),
+    /// Installs a specific branch from a specific remote
+    InstallOrigin(RefOrigin),
+    /// Moves an installed app over to another branch or remote
+    SwitchOrigin(RefOrigin),
+    ListOrigins(PackageId),
+    /// Reads the indexed package and its appstream details for the detail page
+    LoadDetails((Arc<Mutex<Storage>>, PackageId)),
+    /// Looks up what changed since the installed version of each of the updatable apps
+    LoadChangelogs((Arc<Mutex<Storage>>, Vec<Package>)),
+    /// Fetches a screenshot into the cache
+    LoadScreenshot(String),
+    LoadPermissions(PackageId),
+    LoadOverrides((PackageId, InstallationScope)),
+    SetOverride((PackageId, InstallationScope, OverrideChange)),
+    /// Also deletes the data in `~/.var/app` if the flag is set
+    Uninstall((PackageId, InstallationScope, bool)),
+    Launch(PackageId),
+    Update((PackageId, InstallationScope)),
+    UpdateAll,
+    RefreshRuntimes,
+    RemoveUnused,
+    RefreshAppData,
+    DeleteAppData(Vec<PackageId>),
+    LoadHistory((PackageId, InstallationScope)),
+    /// Moves the app to a commit and masks it afterwards if the flag is set
+    Rollback((PackageId, InstallationScope, String, bool)),
+    /// Masks or unmasks an app in its installation
+    SetHeld((PackageId, InstallationScope, bool)),
+    ListBackups(PackageId),
+    CreateBackup(PackageId),
+    RestoreBackup(Backup),
+    /// Path of the manifest and whether to pin the installed commits
+    ExportManifest((PathBuf, bool)),
+    PreviewImport(PathBuf),
+    ApplyImport(Arc<ImportPlan>),
+    /// Diffs a state file against the remotes and installed apps
+    PlanSync(PathBuf),
+    ApplySync(Arc<SyncPlan>),
+    Cancel(OperationId),
     Search((Arc<Mutex<Storage>>, String)),
+    /// Lists the apps of an appstream category, e.g. `Game`
+    Category((Arc<Mutex<Storage>>, String)),
     RefreshStaffPicks(Arc<Mutex<Storage>>),
+    RefreshRemotes,
+    /// Name, location, GPG verification and installation of the remote to add
+    AddRemote(
+        (
+            Arc<Mutex<Storage>>,
+            String,
+            String,
+            Option<GpgVerification>,
+            InstallationScope,
+        ),
+    ),
+    ModifyRemote((Arc<Mutex<Storage>>, RemoteInfo, RemoteChange)),
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     Ready(mpsc::Sender<Action>),
-    Installed(Arc<Vec<Package>>),
+    InstalledApps(Arc<Vec<Package>>),
     Updates(Arc<Vec<Package>>),
+    Eol(Arc<Vec<EolNotice>>),
+    Migrated(EolNotice),
+    /// The releases newer than the installed version, by app
+    Changelogs(Arc<HashMap<PackageId, Vec<Release>>>),
     StaffPicks(Arc<Vec<Package>>),
     Found(Arc<Vec<Package>>),
+    Installed(PackageId),
+    Origins((PackageId, Arc<Vec<RefOrigin>>)),
+    Details((PackageId, Option<Package>, Option<PackageDetails>)),
+    /// A screenshot URL and its cached file
+    Screenshot((String, PathBuf)),
+    ScreenshotFailed((String, BazaarError)),
+    Permissions((PackageId, Arc<Permissions>)),
+    Overrides((PackageId, Arc<Overrides>)),
+    LocalOpened(LocalPackage),
+    OpenFailed((PathBuf, BazaarError)),
+    InstallFailed((PackageId, BazaarError)),
     Uninstalled(PackageId),
+    UninstallFailed((PackageId, BazaarError)),
+    LaunchFailed((PackageId, BazaarError)),
+    Updated(Vec<String>),
+    UpdateFailed(BazaarError),
+    Runtimes(Arc<Vec<RuntimeInfo>>),
+    /// Refs removed by the unused cleanup
+    UnusedRemoved(Vec<String>),
+    /// Data directories of apps that are not installed
+    AppData(Arc<Vec<OrphanedData>>),
+    AppDataDeleted(Vec<PackageId>),
+    Backups((PackageId, Arc<Vec<Backup>>)),
+    History((PackageId, Arc<Vec<Commit>>)),
+    RolledBack((PackageId, InstallationScope)),
+    HeldChanged((PackageId, bool)),
+    BackupCreated(Backup),
+    BackupRestored(Backup),
+    /// Path of the manifest and the number of apps in it
+    ManifestExported((PathBuf, usize)),
+    ImportPlan(Arc<ImportPlan>),
+    /// Refs installed by an import
+    Imported(Vec<String>),
+    SyncPlan(Arc<SyncPlan>),
+    /// The steps of the plan that were applied
+    Synced(Vec<String>),
+    Transaction(TransactionEvent),
+    /// The user aborted the operation, whatever it had not finished was rolled back
+    Cancelled(OperationId),
+    Remotes(Arc<Vec<RemoteInfo>>),
+    /// A remote was added or modified and its packages re-indexed
+    RemotesChanged,
+    Error(BazaarError),
 }
 
-pub fn subscribe() -> iced::Subscription<Message> {
-    iced::Subscription::from_recipe(BackendSubscription)
+pub fn subscribe(screenshots: ScreenshotCache) -> iced::Subscription<Message> {
+    iced::Subscription::from_recipe(BackendSubscription { screenshots })
 }
 
-pub struct BackendSubscription;
+/// Runs `job` on its own thread, its result is reported through `done_tx` once it finishes.
+/// The operation's cancellable stays in `operations` while it is in flight.
+fn spawn_operation<F>(
+    id: OperationId,
+    operations: &Arc<Mutex<HashMap<OperationId, Cancellable>>>,
+    mut done_tx: mpsc::Sender<Message>,
+    job: F,
+) where
+    F: FnOnce(&Operation) -> Message + Send + 'static,
+{
+    let operation = Operation::new(id);
+    operations
+        .lock()
+        .unwrap()
+        .insert(id, operation.cancellable.clone());
+    let operations = operations.clone();
+    std::thread::spawn(move || {
+        let message = job(&operation);
+        operations.lock().unwrap().remove(&operation.id);
+        let message = if operation.is_cancelled() {
+            Message::Cancelled(operation.id)
+        } else {
+            message
+        };
+        let _ = done_tx.try_send(message);
+    });
+}
+
+pub struct BackendSubscription {
+    screenshots: ScreenshotCache,
+}
 
 impl iced_futures::subscription::Recipe for BackendSubscription {
     type Output = Message;
@@ -51,30 +212,455 @@ impl iced_futures::subscription::Recipe for BackendSubscription {
     ) -> iced_futures::BoxStream<Self::Output> {
         use futures::stream::StreamExt;
         let (tx, rx) = mpsc::channel(10);
-        futures::stream::once(async { Message::Ready(tx) })
-            .chain(rx.map(|action| match action {
-                Action::RefreshInstalled => {
-                    let apps = flatpak_backend::get_installed_apps();
-                    Message::Installed(Arc::new(apps))
-                }
+        // Transactions run on their own thread so that the subscription keeps
+        // handling actions, their progress and results are merged back in here.
+        let (progress_tx, progress_rx) = mpsc::channel::<TransactionEvent>(100);
+        let (done_tx, done_rx) = mpsc::channel::<Message>(10);
+        let operations: Arc<Mutex<HashMap<OperationId, Cancellable>>> = Default::default();
+        let mut next_operation: OperationId = 0;
+        let screenshots = self.screenshots;
+        let actions = rx.filter_map(move |action| {
+            next_operation += 1;
+            let id = next_operation;
+            let message = match action {
+                Action::RefreshInstalled => Some(match flatpak_backend::get_installed_apps() {
+                    Ok(apps) => Message::InstalledApps(Arc::new(apps)),
+                    Err(err) => Message::Error(err),
+                }),
                 Action::RefreshUpdates => {
-                    let apps = flatpak_backend::get_updatable_apps();
-                    println!("Found {} updates", apps.len());
-                    Message::Updates(Arc::new(apps))
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match flatpak_backend::get_updatable_apps(&operation.cancellable) {
+                            Ok(apps) => {
+                                println!("Found {} updates", apps.len());
+                                Message::Updates(Arc::new(apps))
+                            }
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::CheckEol => {
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match eol::check_eol(&operation.cancellable) {
+                            Ok(notices) => Message::Eol(Arc::new(notices)),
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::MigrateEol(notice) => {
+                    let progress_tx = progress_tx.clone();
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match eol::migrate(&notice, operation, progress_tx) {
+                            Ok(()) => Message::Migrated(notice),
+                            Err(err) => Message::UpdateFailed(err),
+                        }
+                    });
+                    None
+                }
+                Action::RefreshStaffPicks(db) => Some(match get_staff_picks(db) {
+                    Ok(apps) => Message::StaffPicks(Arc::new(apps)),
+                    Err(err) => Message::Error(err),
+                }),
+                Action::Install(package) => {
+                    let progress_tx = progress_tx.clone();
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match flatpak_backend::install_by_name(&package, operation, progress_tx) {
+                            Ok(()) => Message::Installed(package),
+                            Err(err) => Message::InstallFailed((package, err)),
+                        }
+                    });
+                    None
+                }
+                Action::OpenLocal(path) => {
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match LocalPackage::open(&path, &operation.cancellable) {
+                            Ok(package) => Message::LocalOpened(package),
+                            Err(err) => Message::OpenFailed((path, err)),
+                        }
+                    });
+                    None
+                }
+                Action::InstallLocal(package) => {
+                    let progress_tx = progress_tx.clone();
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match local_packages::install_local(&package, operation, progress_tx) {
+                            Ok(()) => Message::Installed(package.name),
+                            Err(err) => Message::InstallFailed((package.name, err)),
+                        }
+                    });
+                    None
+                }
+                Action::InstallOrigin(origin) => {
+                    let progress_tx = progress_tx.clone();
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match flatpak_backend::install(
+                            &origin.ref_,
+                            &origin.remote,
+                            &origin.scope,
+                            operation,
+                            progress_tx,
+                        ) {
+                            Ok(()) => Message::Installed(origin.name),
+                            Err(err) => Message::InstallFailed((origin.name, err)),
+                        }
+                    });
+                    None
+                }
+                Action::SwitchOrigin(origin) => {
+                    let progress_tx = progress_tx.clone();
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match flatpak_backend::switch_origin(&origin, operation, progress_tx) {
+                            Ok(()) => Message::Installed(origin.name),
+                            Err(err) => Message::InstallFailed((origin.name, err)),
+                        }
+                    });
+                    None
+                }
+                Action::ListOrigins(package) => {
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match flatpak_backend::list_origins(&package, &operation.cancellable) {
+                            Ok(origins) => Message::Origins((package, Arc::new(origins))),
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::LoadPermissions(package) => {
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match permissions::permissions_of(&package, &operation.cancellable) {
+                            Ok(permissions) => {
+                                Message::Permissions((package, Arc::new(permissions)))
+                            }
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::LoadDetails((db, package)) => Some(
+                    match package_by_name(db.clone(), &package, None, None)
+                        .and_then(|found| Ok((found, package_details(db, &package, None, None)?)))
+                    {
+                        Ok((found, details)) => Message::Details((package, found, details)),
+                        Err(err) => Message::Error(err),
+                    },
+                ),
+                Action::LoadChangelogs((db, apps)) => Some(
+                    match apps
+                        .iter()
+                        .map(|app| {
+                            let details = package_details(
+                                db.clone(),
+                                &app.name,
+                                app.remote.as_deref(),
+                                Some(&app.scope),
+                            )?;
+                            let releases = details
+                                .map(|d| releases_since(&d.releases, app.version.as_deref()))
+                                .unwrap_or_default();
+                            Ok((app.name.clone(), releases))
+                        })
+                        .collect::<crate::error::Result<HashMap<_, _>>>()
+                    {
+                        Ok(changelogs) => Message::Changelogs(Arc::new(changelogs)),
+                        Err(err) => Message::Error(err),
+                    },
+                ),
+                Action::LoadScreenshot(url) => {
+                    let screenshots = screenshots.clone();
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match screenshots.get(&url, &operation.cancellable) {
+                            Ok(path) => Message::Screenshot((url, path)),
+                            Err(err) => Message::ScreenshotFailed((url, err)),
+                        }
+                    });
+                    None
+                }
+                Action::Launch(package) => {
+                    match flatpak_backend::launch(&package, &Cancellable::new()) {
+                        Ok(()) => None,
+                        Err(err) => Some(Message::LaunchFailed((package, err))),
+                    }
+                }
+                Action::LoadOverrides((package, scope)) => {
+                    Some(match overrides::load_overrides(&package, &scope) {
+                        Ok(overrides) => Message::Overrides((package, Arc::new(overrides))),
+                        Err(err) => Message::Error(err),
+                    })
                 }
-                Action::RefreshStaffPicks(db) => {
-                    let apps = get_staff_picks(db);
-                    Message::StaffPicks(Arc::new(apps))
+                Action::SetOverride((package, scope, change)) => {
+                    Some(match overrides::apply_override(&package, &scope, &change) {
+                        Ok(overrides) => Message::Overrides((package, Arc::new(overrides))),
+                        Err(err) => Message::Error(err),
+                    })
                 }
-                Action::Uninstall(id) => {
-                    flatpak_backend::uninstall(&id);
-                    Message::Uninstalled(id.clone())
+                Action::Uninstall((package, scope, delete_data)) => {
+                    let progress_tx = progress_tx.clone();
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match flatpak_backend::uninstall(&package, &scope, operation, progress_tx)
+                            .and_then(|()| {
+                                if delete_data {
+                                    app_data::delete_data(&package, &operation.cancellable)
+                                } else {
+                                    Ok(())
+                                }
+                            }) {
+                            Ok(()) => Message::Uninstalled(package),
+                            Err(err) => Message::UninstallFailed((package, err)),
+                        }
+                    });
+                    None
                 }
-                Action::Search((db, st)) => {
-                    let apps = search(db.clone(), &st);
-                    Message::Found(Arc::new(apps))
+                Action::Update((package, scope)) => {
+                    let progress_tx = progress_tx.clone();
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match flatpak_backend::update_by_name(
+                            &package,
+                            &scope,
+                            operation,
+                            progress_tx,
+                        ) {
+                            Ok(refs) => Message::Updated(refs),
+                            Err(err) => Message::UpdateFailed(err),
+                        }
+                    });
+                    None
                 }
-            }))
+                Action::UpdateAll => {
+                    let progress_tx = progress_tx.clone();
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match flatpak_backend::update_all(operation, progress_tx) {
+                            Ok(refs) => Message::Updated(refs),
+                            Err(err) => Message::UpdateFailed(err),
+                        }
+                    });
+                    None
+                }
+                Action::RefreshRuntimes => {
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match runtimes::list_runtimes(&operation.cancellable) {
+                            Ok(runtimes) => Message::Runtimes(Arc::new(runtimes)),
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::RemoveUnused => {
+                    let progress_tx = progress_tx.clone();
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match runtimes::remove_unused(operation, progress_tx) {
+                            Ok(refs) => Message::UnusedRemoved(refs),
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::RefreshAppData => {
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match app_data::find_orphaned(&operation.cancellable) {
+                            Ok(orphaned) => Message::AppData(Arc::new(orphaned)),
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::DeleteAppData(apps) => {
+                    spawn_operation(
+                        id,
+                        &operations,
+                        done_tx.clone(),
+                        move |operation| match apps
+                            .iter()
+                            .try_for_each(|app| app_data::delete_data(app, &operation.cancellable))
+                        {
+                            Ok(()) => Message::AppDataDeleted(apps),
+                            Err(err) => Message::Error(err),
+                        },
+                    );
+                    None
+                }
+                Action::LoadHistory((package, scope)) => {
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match history::commit_history(&package, &scope, &operation.cancellable) {
+                            Ok(commits) => Message::History((package, Arc::new(commits))),
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::Rollback((package, scope, commit, hold)) => {
+                    let progress_tx = progress_tx.clone();
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match history::rollback(&package, &scope, &commit, operation, progress_tx)
+                            .and_then(|()| {
+                                if hold {
+                                    masks::set_masked(
+                                        &scope,
+                                        &package,
+                                        true,
+                                        &operation.cancellable,
+                                    )
+                                } else {
+                                    Ok(())
+                                }
+                            }) {
+                            Ok(()) => Message::RolledBack((package, scope)),
+                            Err(err) => Message::UpdateFailed(err),
+                        }
+                    });
+                    None
+                }
+                Action::SetHeld((package, scope, held)) => Some(
+                    match masks::set_masked(&scope, &package, held, &Cancellable::new()) {
+                        Ok(()) => Message::HeldChanged((package, held)),
+                        Err(err) => Message::Error(err),
+                    },
+                ),
+                Action::ListBackups(package) => Some(match backups::list_backups(&package) {
+                    Ok(list) => Message::Backups((package, Arc::new(list))),
+                    Err(err) => Message::Error(err),
+                }),
+                Action::CreateBackup(package) => {
+                    spawn_operation(id, &operations, done_tx.clone(), move |_| {
+                        match backups::create_backup(&package) {
+                            Ok(backup) => Message::BackupCreated(backup),
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::RestoreBackup(backup) => {
+                    spawn_operation(id, &operations, done_tx.clone(), move |_| {
+                        match backups::restore_backup(&backup) {
+                            Ok(()) => Message::BackupRestored(backup),
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::ExportManifest((path, pin_commits)) => {
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match manifest::export(&path, pin_commits, &operation.cancellable) {
+                            Ok(count) => Message::ManifestExported((path, count)),
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::PreviewImport(path) => {
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match manifest::plan_import(&path, &operation.cancellable) {
+                            Ok(plan) => Message::ImportPlan(Arc::new(plan)),
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::ApplyImport(plan) => {
+                    let progress_tx = progress_tx.clone();
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match manifest::apply_import(&plan, operation, progress_tx) {
+                            Ok(refs) => Message::Imported(refs),
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::PlanSync(path) => {
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match sync::plan_sync(&path, &operation.cancellable) {
+                            Ok(plan) => Message::SyncPlan(Arc::new(plan)),
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::ApplySync(plan) => {
+                    let progress_tx = progress_tx.clone();
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        match sync::apply_sync(&plan, operation, progress_tx) {
+                            Ok(steps) => Message::Synced(steps),
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::Cancel(operation) => {
+                    match operations.lock().unwrap().get(&operation) {
+                        Some(cancellable) => {
+                            println!("Cancelling operation {}", operation);
+                            cancellable.cancel();
+                        }
+                        None => println!("Operation {} already finished", operation),
+                    }
+                    None
+                }
+                Action::RefreshRemotes => Some(match remotes::list_remotes() {
+                    Ok(remotes) => Message::Remotes(Arc::new(remotes)),
+                    Err(err) => Message::Error(err),
+                }),
+                Action::AddRemote((db, name, location, gpg, scope)) => {
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        let cancellable = &operation.cancellable;
+                        match remotes::add_remote(
+                            &name,
+                            &location,
+                            gpg.as_ref(),
+                            &scope,
+                            cancellable,
+                        )
+                        .and_then(|remote| {
+                            let fetched = remotes::fetch_remote(&remote.name, &scope, cancellable)?;
+                            let mut storage = db.lock().unwrap();
+                            remotes::index_remote(
+                                &mut storage,
+                                &remote.name,
+                                &scope,
+                                fetched.as_ref(),
+                            )
+                        }) {
+                            Ok(()) => Message::RemotesChanged,
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::ModifyRemote((db, remote, change)) => {
+                    spawn_operation(id, &operations, done_tx.clone(), move |operation| {
+                        let cancellable = &operation.cancellable;
+                        match remotes::modify_remote(&remote, &change, cancellable).and_then(|()| {
+                            let fetched =
+                                remotes::fetch_remote(&remote.name, &remote.scope, cancellable)?;
+                            let mut storage = db.lock().unwrap();
+                            remotes::index_remote(
+                                &mut storage,
+                                &remote.name,
+                                &remote.scope,
+                                fetched.as_ref(),
+                            )
+                        }) {
+                            Ok(()) => Message::RemotesChanged,
+                            Err(err) => Message::Error(err),
+                        }
+                    });
+                    None
+                }
+                Action::Search((db, st)) => Some(match search(db.clone(), &st) {
+                    Ok(apps) => Message::Found(Arc::new(apps)),
+                    Err(err) => Message::Error(err),
+                }),
+                Action::Category((db, category)) => Some(match apps_in_category(db, &category) {
+                    Ok(apps) => Message::Found(Arc::new(apps)),
+                    Err(err) => Message::Error(err),
+                }),
+            };
+            futures::future::ready(message)
+        });
+        futures::stream::once(async { Message::Ready(tx) })
+            .chain(futures::stream::select(
+                actions,
+                futures::stream::select(progress_rx.map(Message::Transaction), done_rx),
+            ))
             .boxed()
     }
 }
@@ -201,6 +201,18 @@ impl iced::widget::rule::StyleSheet for Theme {
     }
 }
 
+impl iced::widget::progress_bar::StyleSheet for Theme {
+    type Style = ();
+
+    fn appearance(&self, _style: &Self::Style) -> iced_style::progress_bar::Appearance {
+        iced_style::progress_bar::Appearance {
+            background: Background::Color(self.colors().surface_1up),
+            bar: Background::Color(self.colors().primary),
+            border_radius: 3.0.into(),
+        }
+    }
+}
+
 #[derive(Default)]
 pub enum ButtonStyle {
     #[default]
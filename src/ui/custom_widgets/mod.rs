@@ -1,2 +1,3 @@
 pub mod appcard;
+pub mod operation_progress;
 pub mod toast;
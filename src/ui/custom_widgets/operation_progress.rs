@@ -0,0 +1,53 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use iced::{
+    widget::{button, column, progress_bar, row, text},
+    Length,
+};
+
+use crate::{
+    backend::flatpak_backend::{OperationProgress, PackageId},
+    ui::{
+        appearance::{self, ButtonStyle, Theme},
+        main_window::Message,
+    },
+};
+
+/// The progress of the running operation on `id` with a button to cancel it, empty if nothing
+/// is running.
+pub fn operation_progress<'a>(
+    operations: &Mutex<HashMap<PackageId, OperationProgress>>,
+    id: &PackageId,
+) -> iced::Element<'a, Message, iced::Renderer<Theme>> {
+    let Some(progress) = operations
+        .try_lock()
+        .ok()
+        .and_then(|ops| ops.get(id).cloned())
+    else {
+        return column(vec![]).into();
+    };
+    column(vec![
+        text(format!(
+            "{}... {}%",
+            progress.kind.to_string(),
+            progress.percent
+        ))
+        .size(14)
+        .into(),
+        row(vec![
+            progress_bar(0.0..=100.0, progress.percent as f32)
+                .width(Length::Fixed(220.))
+                .height(6)
+                .into(),
+            button(appearance::icon('\u{f0156}'))
+                .on_press(Message::Cancel(progress.operation))
+                .style(ButtonStyle::Icon)
+                .into(),
+        ])
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center)
+        .into(),
+    ])
+    .spacing(4.0)
+    .into()
+}
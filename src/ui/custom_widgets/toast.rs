@@ -10,10 +10,13 @@ use iced::advanced::{Clipboard, Shell, Widget};
 use iced::event::{self, Event};
 use iced::mouse;
 use iced::theme;
-use iced::widget::{button, column, container, horizontal_rule, horizontal_space, row, text};
+use iced::widget::{
+    button, column, container, horizontal_rule, horizontal_space, progress_bar, row, text,
+};
 use iced::window;
 use iced::{Alignment, Element, Length, Point, Rectangle, Size, Vector};
 use iced_core::{Background, Color};
+use crate::backend::flatpak_backend::OperationId;
 use crate::ui::appearance::Theme;
 use crate::ui::appearance::ContainerStyle;
 
@@ -103,6 +106,12 @@ pub struct Toast {
     pub title: String,
     pub body: String,
     pub status: Status,
+    /// Completion in percent, shows a progress bar under the body when set
+    pub progress: Option<f32>,
+    /// The running operation behind the toast, it can be cancelled while in progress
+    pub operation: Option<OperationId>,
+    /// The ref the operation is working on, together with `operation` it identifies the toast
+    pub ref_: Option<String>,
 }
 
 pub struct Manager<'a, Message, Renderer>
@@ -120,19 +129,20 @@ impl<'a, Message, Renderer> Manager<'a, Message, Renderer>
 where
     Message: 'a + Clone,
     Renderer: 'a + renderer::Renderer + iced_core::text::Renderer ,
-    Renderer::Theme: iced::widget::container::StyleSheet + iced::widget::text::StyleSheet + iced::widget::button::StyleSheet + iced::widget::rule::StyleSheet + iced::widget::container::StyleSheet,
+    Renderer::Theme: iced::widget::container::StyleSheet + iced::widget::text::StyleSheet + iced::widget::button::StyleSheet + iced::widget::rule::StyleSheet + iced::widget::container::StyleSheet + iced::widget::progress_bar::StyleSheet,
     <<Renderer as iced_core::Renderer>::Theme as iced_style::container::StyleSheet>::Style: From<ContainerStyle>,
 {
     pub fn new(
         content: impl Into<Element<'a, Message, Renderer>>,
         toasts: &'a [Toast],
         on_close: impl Fn(usize) -> Message + 'a,
+        on_cancel: impl Fn(OperationId) -> Message + 'a,
     ) -> Self {
         let toasts = toasts
             .iter()
             .enumerate()
             .map(|(index, toast)| {
-                container(column![
+                let mut content = column![
                     container(
                         row![
                             text(toast.title.as_str()),
@@ -149,9 +159,18 @@ where
                         .width(Length::Fill)
                         .padding(5)
                         // .style(theme::Container::Box),
-                ])
-                .max_width(200)
-                .into()
+                ];
+                if let Some(progress) = toast.progress {
+                    let mut progress_row = row![progress_bar(0.0..=100.0, progress).height(6)]
+                        .spacing(5)
+                        .align_items(Alignment::Center);
+                    if let (Some(operation), true) = (toast.operation, progress < 100.0) {
+                        progress_row = progress_row
+                            .push(button("Cancel").on_press((on_cancel)(operation)).padding(3));
+                    }
+                    content = content.push(container(progress_row).width(Length::Fill).padding(5));
+                }
+                container(content).max_width(200).into()
             })
             .collect();
 
@@ -4,33 +4,55 @@ use iced::{
     futures::{channel::mpsc, StreamExt},
     keyboard::{self, Modifiers},
     subscription,
-    widget::{button, column, container, text},
+    widget::{button, column, container, horizontal_space, row, text},
     window, Alignment, Application, Color, Element, Event, Length, Settings,
 };
+use libflatpak::gio;
 
 use std::{
     borrow::{Borrow, BorrowMut},
     cell::RefCell,
+    collections::HashMap,
     path::PathBuf,
     sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
 
 use crate::{
-    backend::{self, flatpak_backend::PackageId},
+    backend::{
+        self,
+        backups::Backup,
+        eol::EolNotice,
+        flatpak_backend::{
+            InstallationScope, OperationId, OperationProgress, PackageId, RefOrigin,
+            TransactionEvent,
+        },
+        manifest::ManifestFormat,
+        overrides::OverrideChange,
+        remotes::{RemoteChange, RemoteInfo},
+        screenshots::{self, GioFetcher, ScreenshotCache},
+    },
     db::{self, Storage},
+    error::BazaarError,
 };
 
 use super::{
-    action, appearance,
+    action,
+    appearance::{self, ButtonStyle, ContainerStyle},
     custom_widgets::toast::{self, Status, Toast},
-    tabs::app_view::AppView,
+    tabs::app_view::{AppView, AppViewMessage},
 };
 use super::{
     appearance::Theme,
     tabs::{
+        app_data_page::{AppDataPage, AppDataPageMessage},
         installed_page::{InstalledPage, InstalledPageMessage},
         landing_page::{LandingPage, LandingPageMessage},
+        local_install_page::{LocalInstallPage, LocalInstallPageMessage},
+        manifest_page::{ManifestPage, ManifestPageMessage},
+        remotes_page::{RemotesPage, RemotesPageMessage},
+        runtimes_page::{RuntimesPage, RuntimesPageMessage},
+        sync_page::{SyncPage, SyncPageMessage},
         Tab,
     },
 };
@@ -38,8 +60,10 @@ use super::{
 use once_cell::sync::Lazy;
 static CONTAINER: Lazy<id::Container> = Lazy::new(id::Container::unique);
 
-pub fn run() -> iced::Result {
+/// `file` is a `.flatpakref` or `.flatpak` file to offer for installation right away.
+pub fn run(file: Option<PathBuf>) -> iced::Result {
     BazaarApp::run(Settings {
+        flags: file,
         // default_font: Some(appearance::NOTO_SANS),
         window: window::Settings {
             decorations: true,
@@ -53,6 +77,8 @@ pub fn run() -> iced::Result {
 #[derive(Clone)]
 pub struct Config {
     pub dark_mode: bool,
+    /// Upper bound of the screenshot cache in bytes
+    pub screenshot_cache_size: u64,
 }
 
 struct BazaarApp {
@@ -65,11 +91,29 @@ struct BazaarApp {
     landing_page: LandingPage,
     installed_page: InstalledPage,
     app_view_page: AppView,
+    remotes_page: RemotesPage,
+    local_install_page: LocalInstallPage,
+    runtimes_page: RuntimesPage,
+    app_data_page: AppDataPage,
+    manifest_page: ManifestPage,
+    sync_page: SyncPage,
+    /// The app waiting for the user to confirm its removal
+    pending_uninstall: Option<(PackageId, InstallationScope)>,
+    /// Whether confirming the removal also deletes the data of the app
+    delete_app_data: bool,
+    /// The unused runtimes and extensions waiting for the user to confirm their removal
+    pending_remove_unused: Option<Vec<String>>,
+    /// A file or manifest opened before the backend was ready, e.g. from the command line
+    pending_file: Option<PathBuf>,
+    screenshots: ScreenshotCache,
     active_tab: usize,
     timeline: Timeline,
     current_page: Page,
     toasts: Vec<Toast>,
     timeout_secs: u64,
+    operations: Arc<Mutex<HashMap<PackageId, OperationProgress>>>,
+    /// Refs each running operation already finished, reported if it gets cancelled
+    completed: HashMap<OperationId, Vec<String>>,
 }
 
 #[derive(Debug, Clone)]
@@ -77,6 +121,12 @@ pub enum Page {
     LandingPage,
     Installed,
     Detail,
+    Remotes,
+    LocalInstall,
+    Runtimes,
+    AppData,
+    Manifest,
+    Sync,
 }
 
 #[derive(Debug, Clone)]
@@ -85,12 +135,64 @@ pub enum Message {
     RequestRefreshInstalledApps,
     RequestRefreshUpdates,
     RequestRefreshStaffPickApps,
+    RequestRefreshRemotes,
+    RequestRefreshRuntimes,
+    /// Asks for confirmation before removing the listed unused runtimes and extensions
+    RemoveUnused(Vec<String>),
+    ConfirmRemoveUnused,
+    CancelRemoveUnused,
+    RequestRefreshAppData,
+    /// Writes the installed apps to the path on the manifest page
+    ExportManifest,
+    /// Works out what importing the manifest on the manifest page would install
+    PreviewImport,
+    ApplyImport,
+    ManifestPage(ManifestPageMessage),
+    /// Diffs the state file on the sync page against the machine
+    PlanSync,
+    ApplySync,
+    SyncPage(SyncPageMessage),
+    DeleteAppData(Vec<PackageId>),
+    /// App, installation, commit and whether to hold the app at that commit
+    Rollback((PackageId, InstallationScope, String, bool)),
+    /// Installs the app an end-of-life app was renamed to in its place
+    MigrateEol(EolNotice),
+    /// Holds back or releases the updates of an app
+    SetHeld((PackageId, InstallationScope, bool)),
+    CreateBackup(PackageId),
+    RestoreBackup(Backup),
     Install(PackageId),
-    Uninstall(PackageId),
+    /// Opens a `.flatpakref` or `.flatpak` file on the confirmation page
+    OpenFile(PathBuf),
+    /// Asks for the file to open with a file chooser
+    PickFile,
+    FilePicked(Option<PathBuf>),
+    InstallLocal,
+    InstallOrigin(RefOrigin),
+    SwitchOrigin(RefOrigin),
+    SetOverride(OverrideChange),
+    AppView(AppViewMessage),
+    LocalInstallPage(LocalInstallPageMessage),
+    /// Asks for confirmation before uninstalling
+    Uninstall((PackageId, InstallationScope)),
+    ToggleDeleteAppData,
+    ConfirmUninstall,
+    CancelUninstall,
+    Launch(PackageId),
+    Update((PackageId, InstallationScope)),
+    UpdateAll,
+    Cancel(OperationId),
+    /// Opens a link, e.g. the homepage of an app, in the default browser
+    OpenUrl(String),
+    AddRemote,
+    ModifyRemote((RemoteInfo, RemoteChange)),
+    RemotesPage(RemotesPageMessage),
     Detail(PackageId),
     ActionMessage(action::Message),
     DBMessage(db::Message),
     Search(String),
+    /// Browses an appstream category, or stops browsing it when it is selected already
+    Category(String),
     SearchButton,
     IncreaseScalingFactor,
     DecreaseScalingFactor,
@@ -101,6 +203,224 @@ pub enum Message {
     StopSearch,
 }
 
+impl BazaarApp {
+    fn show_error(&mut self, title: &str, err: &BazaarError) {
+        eprintln!("{}: {}", title, err);
+        self.toasts.push(Toast {
+            title: title.into(),
+            body: err.to_string(),
+            status: Status::Danger,
+            progress: None,
+            operation: None,
+            ref_: None,
+        });
+    }
+
+    /// The package database, or `None` with a toast saying so if it is still loading.
+    fn loaded_db(&mut self) -> Option<Arc<Mutex<Storage>>> {
+        if self.db.is_none() {
+            self.toasts.push(Toast {
+                title: "Still loading".into(),
+                body: "The package database is not ready yet, try again in a moment".into(),
+                status: Status::Secondary,
+                progress: None,
+                operation: None,
+                ref_: None,
+            });
+        }
+        self.db.clone()
+    }
+
+    /// Asks whether to uninstall the app picked for removal and whether its data goes too
+    fn uninstall_dialog(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let Some((id, scope)) = &self.pending_uninstall else {
+            return column(vec![]).into();
+        };
+        container(
+            row(vec![
+                text(format!("Uninstall {} ({})?", id, scope.to_string()))
+                    .size(20)
+                    .into(),
+                horizontal_space(Length::Fill).into(),
+                button(text(format!("Also delete ~/.var/app/{}", id)))
+                    .style(if self.delete_app_data {
+                        ButtonStyle::Primary
+                    } else {
+                        ButtonStyle::Secondary
+                    })
+                    .padding([10, 20])
+                    .on_press(Message::ToggleDeleteAppData)
+                    .into(),
+                button(text("Uninstall"))
+                    .style(ButtonStyle::Primary)
+                    .padding([10, 20])
+                    .on_press(Message::ConfirmUninstall)
+                    .into(),
+                button(text("Cancel"))
+                    .style(ButtonStyle::Secondary)
+                    .padding([10, 20])
+                    .on_press(Message::CancelUninstall)
+                    .into(),
+            ])
+            .spacing(10.0)
+            .align_items(Alignment::Center),
+        )
+        .padding(10.0)
+        .style(ContainerStyle::Section)
+        .into()
+    }
+
+    /// Asks whether to remove the runtimes and extensions no app needs anymore
+    fn remove_unused_dialog(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let Some(names) = &self.pending_remove_unused else {
+            return column(vec![]).into();
+        };
+        container(
+            row(vec![
+                text(format!(
+                    "Remove {} unused runtimes and extensions? {}",
+                    names.len(),
+                    names.join(", ")
+                ))
+                .size(20)
+                .width(Length::Fill)
+                .into(),
+                button(text("Remove"))
+                    .style(ButtonStyle::Primary)
+                    .padding([10, 20])
+                    .on_press(Message::ConfirmRemoveUnused)
+                    .into(),
+                button(text("Cancel"))
+                    .style(ButtonStyle::Secondary)
+                    .padding([10, 20])
+                    .on_press(Message::CancelRemoveUnused)
+                    .into(),
+            ])
+            .spacing(10.0)
+            .align_items(Alignment::Center),
+        )
+        .padding(10.0)
+        .style(ContainerStyle::Section)
+        .into()
+    }
+
+    /// Fetches the screenshots the detail page is about to show
+    fn fetch_screenshots(&mut self) {
+        for url in self.app_view_page.screenshots_to_fetch() {
+            let _ = self
+                .action
+                .as_mut()
+                .map(|tx| tx.start_send(action::Action::LoadScreenshot(url)));
+        }
+    }
+
+    /// Keeps the per-operation progress shown on the cards and toasts in sync
+    fn transaction_event(&mut self, event: TransactionEvent) {
+        let mut operations = self.operations.lock().unwrap();
+        match event {
+            TransactionEvent::NewOperation(progress) => {
+                self.toasts.push(Toast {
+                    title: format!("{} {}", progress.kind.to_string(), progress.package_id()),
+                    body: progress.ref_.clone(),
+                    status: Status::Primary,
+                    progress: Some(0.0),
+                    operation: Some(progress.operation),
+                    ref_: Some(progress.ref_.clone()),
+                });
+                self.completed.retain(|operation, _| {
+                    *operation == progress.operation
+                        || operations.values().any(|p| p.operation == *operation)
+                });
+                operations.insert(progress.package_id(), progress);
+            }
+            TransactionEvent::Progress(progress) => {
+                if let Some(toast) = operation_toast(&mut self.toasts, &progress) {
+                    toast.progress = Some(progress.percent as f32);
+                }
+                operations.insert(progress.package_id(), progress);
+            }
+            TransactionEvent::OperationDone(progress) => {
+                if let Some(toast) = operation_toast(&mut self.toasts, &progress) {
+                    toast.progress = Some(100.0);
+                    toast.status = Status::Success;
+                }
+                self.completed
+                    .entry(progress.operation)
+                    .or_default()
+                    .push(progress.ref_.clone());
+                operations.remove(&progress.package_id());
+            }
+            TransactionEvent::OperationError((progress, err)) => {
+                if let Some(toast) = operation_toast(&mut self.toasts, &progress) {
+                    toast.progress = None;
+                    toast.status = Status::Danger;
+                    toast.body = format!("{}: {}", progress.ref_, err);
+                }
+                operations.remove(&progress.package_id());
+            }
+        }
+    }
+
+    /// Reports what was kept of a cancelled operation and clears its progress
+    fn operation_cancelled(&mut self, operation: OperationId) {
+        println!("Cancelled operation {}", operation);
+        self.operations
+            .lock()
+            .unwrap()
+            .retain(|_, progress| progress.operation != operation);
+        for toast in self
+            .toasts
+            .iter_mut()
+            .filter(|t| t.operation == Some(operation) && t.status == Status::Primary)
+        {
+            toast.progress = None;
+            toast.status = Status::Secondary;
+        }
+        let kept = self.completed.remove(&operation).unwrap_or_default();
+        let body = if kept.is_empty() {
+            "Nothing was changed".to_string()
+        } else {
+            format!(
+                "Unfinished steps were rolled back, kept: {}",
+                kept.join(", ")
+            )
+        };
+        self.toasts.push(Toast {
+            title: "Cancelled".into(),
+            body,
+            status: Status::Secondary,
+            progress: None,
+            operation: None,
+            ref_: None,
+        });
+        let _ = self
+            .action
+            .as_mut()
+            .map(|tx| tx.start_send(action::Action::RefreshInstalled));
+    }
+}
+
+/// Lets the user choose a `.flatpakref` or `.flatpak` file to install.
+async fn pick_file() -> Option<PathBuf> {
+    rfd::AsyncFileDialog::new()
+        .set_title("Install a Flatpak file")
+        .add_filter("Flatpak files", &["flatpakref", "flatpak"])
+        .pick_file()
+        .await
+        .map(|file| file.path().to_path_buf())
+}
+
+/// The toast of the operation `progress` belongs to, two operations on the same ref each have
+/// their own.
+fn operation_toast<'a>(
+    toasts: &'a mut [Toast],
+    progress: &OperationProgress,
+) -> Option<&'a mut Toast> {
+    toasts.iter_mut().find(|t| {
+        t.operation == Some(progress.operation) && t.ref_.as_ref() == Some(&progress.ref_)
+    })
+}
+
 impl Application for BazaarApp {
     type Executor = executor::Default;
 
@@ -108,7 +428,7 @@ impl Application for BazaarApp {
 
     type Theme = Theme;
 
-    type Flags = ();
+    type Flags = Option<PathBuf>;
 
     fn theme(&self) -> Self::Theme {
         if self.config.dark_mode {
@@ -118,12 +438,17 @@ impl Application for BazaarApp {
         }
     }
 
-    fn new(_flags: Self::Flags) -> (Self, iced::Command<Self::Message>) {
+    fn new(file: Self::Flags) -> (Self, iced::Command<Self::Message>) {
         // let mut db = Storage::new().unwrap();
         // db.create_table().unwrap();
         // db.all_packages = Some(db.all_names().unwrap());
-        let config = Config { dark_mode: true };
+        let config = Config {
+            dark_mode: true,
+            screenshot_cache_size: screenshots::DEFAULT_CACHE_SIZE,
+        };
+        let screenshots = ScreenshotCache::new(Arc::new(GioFetcher), config.screenshot_cache_size);
         let db = None;
+        let operations: Arc<Mutex<HashMap<PackageId, OperationProgress>>> = Default::default();
         let mut timeline = Timeline::new();
         let animation = chain![
             CONTAINER,
@@ -131,6 +456,8 @@ impl Application for BazaarApp {
             c(Duration::from_millis(700)).width(800.),
         ];
         timeline.set_chain(animation).start();
+        let installed_page = InstalledPage::new(config.clone(), operations.clone());
+        let installed_apps = installed_page.installed_apps.clone();
 
         (
             BazaarApp {
@@ -140,9 +467,20 @@ impl Application for BazaarApp {
                 db_stream: None,
                 db_progress: None,
                 scaling_factor: 1.0,
-                landing_page: LandingPage::new(config.clone()),
-                installed_page: InstalledPage::new(config.clone()),
-                app_view_page: AppView::new(config.clone()),
+                landing_page: LandingPage::new(config.clone(), operations.clone()),
+                installed_page,
+                app_view_page: AppView::new(config.clone(), operations.clone(), installed_apps),
+                remotes_page: RemotesPage::new(config.clone()),
+                local_install_page: LocalInstallPage::new(config.clone()),
+                runtimes_page: RuntimesPage::new(config.clone()),
+                app_data_page: AppDataPage::new(config.clone()),
+                manifest_page: ManifestPage::new(config.clone()),
+                sync_page: SyncPage::new(config.clone()),
+                pending_uninstall: None,
+                pending_file: None,
+                screenshots,
+                delete_app_data: false,
+                pending_remove_unused: None,
                 active_tab: Default::default(),
                 timeline,
                 current_page: Page::LandingPage,
@@ -150,11 +488,22 @@ impl Application for BazaarApp {
                     title: "Loading...".into(),
                     body: "Updating the database. Please wait...".into(),
                     status: Status::Primary,
+                    progress: None,
+                    operation: None,
+                    ref_: None,
                 }],
                 timeout_secs: 30, /* toast::DEFAULT_TIMEOUT */
+                operations,
+                completed: HashMap::new(),
             },
-            iced::font::load(include_bytes!("../../fonts/nerd_font.ttf").as_slice())
-                .map(Message::FontLoaded),
+            iced::Command::batch(vec![
+                iced::font::load(include_bytes!("../../fonts/nerd_font.ttf").as_slice())
+                    .map(Message::FontLoaded),
+                match file {
+                    Some(path) => iced::Command::perform(async { path }, Message::OpenFile),
+                    None => iced::Command::none(),
+                },
+            ]),
         )
     }
 
@@ -164,7 +513,7 @@ impl Application for BazaarApp {
 
     fn subscription(&self) -> iced::Subscription<Self::Message> {
         iced::Subscription::batch([
-            action::subscribe().map(Message::ActionMessage),
+            action::subscribe(self.screenshots.clone()).map(Message::ActionMessage),
             db::subscribe().map(Message::DBMessage),
             self.landing_page
                 .timeline
@@ -195,6 +544,9 @@ impl Application for BazaarApp {
                     }),
                     event::Status::Ignored,
                 ) => Some(Message::IncreaseScalingFactor),
+                (Event::Window(window::Event::FileDropped(path)), event::Status::Ignored) => {
+                    Some(Message::OpenFile(path))
+                }
                 (
                     Event::Keyboard(keyboard::Event::KeyPressed {
                         key_code: keyboard::KeyCode::Escape,
@@ -215,6 +567,9 @@ impl Application for BazaarApp {
                         .landing_page
                         .update(LandingPageMessage::DBLoadProgress(p));
                 }
+                Ok(Some(db::Message::Error(err))) => {
+                    self.show_error("Unable to load a remote", &err);
+                }
                 _ => {}
             };
         }
@@ -232,35 +587,299 @@ impl Application for BazaarApp {
                     .map(|tx| tx.start_send(action::Action::RefreshUpdates));
             }
             Message::RequestRefreshStaffPickApps => {
-                let _ = self.action.as_mut().map(|tx| {
-                    tx.start_send(action::Action::RefreshStaffPicks(
-                        self.db.as_ref().unwrap().clone(),
-                    ))
-                });
+                if let Some(db) = self.loaded_db() {
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::RefreshStaffPicks(db)));
+                }
+            }
+            Message::RequestRefreshRemotes => {
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::RefreshRemotes));
+            }
+            Message::RequestRefreshRuntimes => {
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::RefreshRuntimes));
+            }
+            Message::RemoveUnused(names) => self.pending_remove_unused = Some(names),
+            Message::CancelRemoveUnused => self.pending_remove_unused = None,
+            Message::ConfirmRemoveUnused => {
+                if self.pending_remove_unused.take().is_some() {
+                    println!("Removing unused runtimes and extensions");
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::RemoveUnused));
+                }
             }
             Message::Install(id) => {
                 println!("Installing {}", id);
-                // TODO
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::Install(id.clone())));
+            }
+            // Manifests of installed apps are offered for import instead
+            Message::OpenFile(path) if ManifestFormat::of(&path).is_ok() => {
+                println!("Opened manifest {}", path.display());
+                match self.action.as_mut() {
+                    Some(tx) => {
+                        let _ = tx.start_send(action::Action::PreviewImport(path));
+                    }
+                    None => self.pending_file = Some(path),
+                }
+                self.current_page = Page::Manifest;
+            }
+            Message::OpenFile(path) => match self.action.as_mut() {
+                Some(tx) => {
+                    let _ = tx.start_send(action::Action::OpenLocal(path));
+                }
+                None => self.pending_file = Some(path),
+            },
+            Message::PickFile => return iced::Command::perform(pick_file(), Message::FilePicked),
+            Message::FilePicked(Some(path)) => return self.update(Message::OpenFile(path)),
+            // The chooser was closed without picking a file
+            Message::FilePicked(None) => (),
+            Message::InstallLocal => {
+                if let Some(package) = self.local_install_page.package.take() {
+                    println!(
+                        "Installing {} from {}",
+                        package.name,
+                        package.path.display()
+                    );
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::InstallLocal(package)));
+                }
+                self.current_page = Page::LandingPage;
+            }
+            Message::LocalInstallPage(msg) => self.local_install_page.update(msg),
+            Message::SetOverride(change) => {
+                if let Some((id, scope)) = self.app_view_page.override_target() {
+                    let _ = self.action.as_mut().map(|tx| {
+                        tx.start_send(action::Action::SetOverride((id, scope, change.clone())))
+                    });
+                }
+            }
+            Message::AppView(msg) => {
+                let reload = matches!(msg, AppViewMessage::OverrideScope(_));
+                self.app_view_page.update(msg);
+                self.fetch_screenshots();
+                if let (true, Some(target)) = (reload, self.app_view_page.override_target()) {
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::LoadOverrides(target.clone())));
+                }
+            }
+            Message::InstallOrigin(origin) => {
+                println!("Installing {} from {}", origin.ref_, origin.remote);
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::InstallOrigin(origin.clone())));
+            }
+            Message::SwitchOrigin(origin) => {
+                println!(
+                    "Switching {} to {} from {}",
+                    origin.name, origin.ref_, origin.remote
+                );
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::SwitchOrigin(origin.clone())));
+            }
+            Message::Uninstall(target) => {
+                self.pending_uninstall = Some(target);
+                self.delete_app_data = false;
+            }
+            Message::ToggleDeleteAppData => self.delete_app_data = !self.delete_app_data,
+            Message::CancelUninstall => self.pending_uninstall = None,
+            Message::ConfirmUninstall => {
+                if let Some((id, scope)) = self.pending_uninstall.take() {
+                    println!("Uninstalling {} ({})", id, scope.to_string());
+                    let uninstall = action::Action::Uninstall((id, scope, self.delete_app_data));
+                    let _ = self.action.as_mut().map(|tx| tx.start_send(uninstall));
+                }
+            }
+            Message::ExportManifest => {
+                let page = &self.manifest_page;
+                let export = action::Action::ExportManifest((
+                    PathBuf::from(&page.export_path),
+                    page.pin_commits,
+                ));
+                let _ = self.action.as_mut().map(|tx| tx.start_send(export));
+            }
+            Message::PreviewImport => {
+                let path = PathBuf::from(&self.manifest_page.import_path);
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::PreviewImport(path)));
+            }
+            Message::ApplyImport => {
+                if let Some(plan) = self.manifest_page.plan.take() {
+                    println!("Importing {}", plan.path.display());
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::ApplyImport(plan)));
+                }
+            }
+            Message::ManifestPage(msg) => self.manifest_page.update(msg),
+            Message::PlanSync => {
+                let path = PathBuf::from(&self.sync_page.state_path);
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::PlanSync(path)));
+            }
+            Message::ApplySync => {
+                if let Some(plan) = self.sync_page.plan.take() {
+                    println!("Syncing to {}", plan.path.display());
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::ApplySync(plan)));
+                }
+            }
+            Message::SyncPage(msg) => self.sync_page.update(msg),
+            Message::RequestRefreshAppData => {
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::RefreshAppData));
+            }
+            Message::Rollback((id, scope, commit, hold)) => {
+                println!("Rolling {} back to {}", id, commit);
+                let rollback = action::Action::Rollback((id, scope, commit, hold));
+                let _ = self.action.as_mut().map(|tx| tx.start_send(rollback));
+            }
+            Message::MigrateEol(notice) => {
+                println!("Migrating {} to {:?}", notice.app, notice.rebase);
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::MigrateEol(notice)));
+            }
+            Message::SetHeld((id, scope, held)) => {
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::SetHeld((id, scope, held))));
+            }
+            Message::CreateBackup(id) => {
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::CreateBackup(id)));
+            }
+            Message::RestoreBackup(backup) => {
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::RestoreBackup(backup)));
+            }
+            Message::DeleteAppData(apps) => {
+                println!("Deleting the data of {:?}", apps);
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::DeleteAppData(apps)));
+            }
+            Message::Update((id, scope)) => {
+                println!("Updating {} ({})", id, scope.to_string());
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::Update((id.clone(), scope.clone()))));
+            }
+            Message::UpdateAll => {
+                println!("Updating everything");
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::UpdateAll));
+            }
+            Message::Launch(id) => {
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::Launch(id)));
+            }
+            Message::OpenUrl(url) => {
+                if let Err(err) =
+                    gio::AppInfo::launch_default_for_uri(&url, None::<&gio::AppLaunchContext>)
+                {
+                    self.show_error(&format!("Unable to open {}", url), &err.into());
+                }
             }
-            Message::Uninstall(id) => {
-                println!("Uninstalling {}", id);
+            Message::Cancel(operation) => {
+                println!("Cancelling operation {}", operation);
                 let _ = self
                     .action
                     .as_mut()
-                    .map(|tx| tx.start_send(action::Action::Uninstall(id.clone())));
+                    .map(|tx| tx.start_send(action::Action::Cancel(operation)));
             }
+            Message::AddRemote => {
+                let Some(db) = self.loaded_db() else {
+                    return iced::Command::none();
+                };
+                let page = &self.remotes_page;
+                println!("Adding remote {} from {}", page.name, page.location);
+                let _ = self.action.as_mut().map(|tx| {
+                    tx.start_send(action::Action::AddRemote((
+                        db,
+                        page.name.clone(),
+                        page.location.clone(),
+                        page.gpg_verification(),
+                        page.scope.clone(),
+                    )))
+                });
+            }
+            Message::ModifyRemote((remote, change)) => {
+                let Some(db) = self.loaded_db() else {
+                    return iced::Command::none();
+                };
+                let _ = self.action.as_mut().map(|tx| {
+                    tx.start_send(action::Action::ModifyRemote((
+                        db,
+                        remote.clone(),
+                        change.clone(),
+                    )))
+                });
+            }
+            Message::RemotesPage(msg) => self.remotes_page.update(msg),
             Message::Search(st) => {
-                if st.len() >= 3 {
+                // Nothing to search before the database is loaded, the landing page says so
+                if let Some(db) = self.db.clone().filter(|_| st.len() >= 3) {
                     println!("searching for {}", st);
-                    let _ = self.action.as_mut().map(|tx| {
-                        tx.start_send(action::Action::Search((
-                            self.db.as_ref().unwrap().clone(),
-                            st.clone(),
-                        )))
-                    });
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::Search((db, st.clone()))));
                 }
                 let _ = self.landing_page.update(LandingPageMessage::Search(st));
             }
+            Message::Category(category) => {
+                if self.landing_page.category.as_ref() == Some(&category) {
+                    let _ = self.landing_page.update(LandingPageMessage::Category(None));
+                } else if let Some(db) = self.loaded_db() {
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::Category((db, category.clone()))));
+                    let _ = self
+                        .landing_page
+                        .update(LandingPageMessage::Category(Some(category)));
+                }
+            }
             Message::StopSearch => {
                 let _ = self.landing_page.update(LandingPageMessage::StopSearch);
             }
@@ -275,9 +894,47 @@ impl Application for BazaarApp {
             }
             Message::Detail(id) => {
                 println!("Show detail for the app: {id:?}");
+                self.app_view_page.update(AppViewMessage::Open(id.clone()));
+                if let Some(db) = self.db.as_ref() {
+                    let load = action::Action::LoadDetails((db.clone(), id.clone()));
+                    let _ = self.action.as_mut().map(|tx| tx.start_send(load));
+                }
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::ListOrigins(id.clone())));
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::LoadPermissions(id.clone())));
+                let _ = self
+                    .action
+                    .as_mut()
+                    .map(|tx| tx.start_send(action::Action::ListBackups(id.clone())));
+                if let Some(target) = self.app_view_page.installed_target() {
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::LoadHistory(target)));
+                }
+                if let Some(target) = self.app_view_page.override_target() {
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::LoadOverrides(target.clone())));
+                }
                 self.current_page = Page::Detail;
             }
             Message::ChangePage(page) => {
+                let refresh = match page {
+                    Page::Remotes => Some(action::Action::RefreshRemotes),
+                    Page::Runtimes => Some(action::Action::RefreshRuntimes),
+                    Page::AppData => Some(action::Action::RefreshAppData),
+                    _ => None,
+                };
+                if let Some(refresh) = refresh {
+                    let _ = self.action.as_mut().map(|tx| tx.start_send(refresh));
+                }
                 self.current_page = page;
             }
             Message::Close(index) => {
@@ -294,14 +951,14 @@ impl Application for BazaarApp {
                         .map(|dbtx| dbtx.start_send(db::Action::Load(tx)));
                 }
                 db::Message::Progress(_) => {}
+                db::Message::Error(err) => self.show_error("Unable to load the database", &err),
                 db::Message::Loaded(db) => {
-                    self.db = Some(db);
+                    self.db = Some(db.clone());
                     let _ = self.landing_page.update(LandingPageMessage::DBLoaded);
-                    let _ = self.action.as_mut().map(|tx| {
-                        tx.start_send(action::Action::RefreshStaffPicks(
-                            self.db.as_ref().unwrap().clone(),
-                        ))
-                    });
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::RefreshStaffPicks(db)));
                     let _ = self
                         .action
                         .as_mut()
@@ -315,14 +972,70 @@ impl Application for BazaarApp {
             Message::ActionMessage(msg) => match msg {
                 action::Message::Ready(tx) => {
                     self.action = Some(tx);
+                    if let Some(path) = self.pending_file.take() {
+                        return self.update(Message::OpenFile(path));
+                    }
+                }
+                action::Message::LocalOpened(package) => {
+                    println!("Opened {} from {}", package.name, package.path.display());
+                    self.local_install_page
+                        .update(LocalInstallPageMessage::Opened(package));
+                    self.current_page = Page::LocalInstall;
                 }
-                action::Message::Installed(apps) => {
+                action::Message::OpenFailed((path, err)) => {
+                    self.show_error(&format!("Unable to open {}", path.display()), &err)
+                }
+                action::Message::InstalledApps(apps) => {
                     self.installed_page
                         .update(InstalledPageMessage::Installed(apps));
+                    // Installing or removing apps changes which runtimes are on disk too
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::RefreshRuntimes));
                 }
                 action::Message::Updates(apps) => {
+                    if let Some(db) = self.db.as_ref() {
+                        let load = action::Action::LoadChangelogs((db.clone(), apps.to_vec()));
+                        let _ = self.action.as_mut().map(|tx| tx.start_send(load));
+                    }
                     self.installed_page
                         .update(InstalledPageMessage::Updates(apps));
+                    // Remotes publish end-of-life markers with the same metadata as updates
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::CheckEol));
+                }
+                action::Message::Eol(notices) => {
+                    self.installed_page
+                        .update(InstalledPageMessage::Eol(notices));
+                }
+                action::Message::Migrated(notice) => {
+                    self.toasts.push(Toast {
+                        title: "Migrated".into(),
+                        body: format!(
+                            "{} was replaced by {}",
+                            notice.app,
+                            notice.rebase_name().unwrap_or_default()
+                        ),
+                        status: Status::Success,
+                        progress: None,
+                        operation: None,
+                        ref_: None,
+                    });
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::RefreshInstalled));
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::RefreshUpdates));
+                }
+                action::Message::Changelogs(changelogs) => {
+                    self.installed_page
+                        .update(InstalledPageMessage::Changelogs(changelogs));
                 }
                 action::Message::StaffPicks(apps) => {
                     let _ = self
@@ -332,6 +1045,57 @@ impl Application for BazaarApp {
                 action::Message::Found(apps) => {
                     return self.landing_page.update(LandingPageMessage::Found(apps));
                 }
+                action::Message::Installed(id) => {
+                    println!("Installed {:?}", id);
+                    self.toasts.push(Toast {
+                        title: "Installed".into(),
+                        body: format!("{} was installed successfully", id),
+                        status: Status::Success,
+                        progress: None,
+                        operation: None,
+                        ref_: None,
+                    });
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::RefreshInstalled));
+                    if let Page::Detail = self.current_page {
+                        // The installed branch shown on the detail page changed
+                        let _ = self
+                            .action
+                            .as_mut()
+                            .map(|tx| tx.start_send(action::Action::ListOrigins(id.clone())));
+                    }
+                }
+                action::Message::Origins((id, origins)) => {
+                    self.app_view_page
+                        .update(AppViewMessage::Origins((id, origins)));
+                }
+                action::Message::Details(details) => {
+                    self.app_view_page.update(AppViewMessage::Loaded(details));
+                    self.fetch_screenshots();
+                }
+                action::Message::Screenshot(screenshot) => {
+                    self.app_view_page
+                        .update(AppViewMessage::ScreenshotLoaded(screenshot));
+                }
+                action::Message::ScreenshotFailed((url, err)) => {
+                    // The carousel offers a retry, not worth a toast
+                    eprintln!("Unable to load screenshot {}: {}", url, err);
+                    self.app_view_page
+                        .update(AppViewMessage::ScreenshotFailed(url));
+                }
+                action::Message::Permissions((id, permissions)) => {
+                    self.app_view_page
+                        .update(AppViewMessage::Permissions((id, permissions)));
+                }
+                action::Message::Overrides((id, overrides)) => {
+                    self.app_view_page
+                        .update(AppViewMessage::Overrides((id, overrides)));
+                }
+                action::Message::InstallFailed((id, err)) => {
+                    self.show_error(&format!("Unable to install {}", id), &err);
+                }
                 action::Message::Uninstalled(id) => {
                     println!("Uninstalled {:?}", id);
                     let _ = self
@@ -339,6 +1103,208 @@ impl Application for BazaarApp {
                         .as_mut()
                         .map(|tx| tx.start_send(action::Action::RefreshInstalled));
                 }
+                action::Message::Updated(refs) => {
+                    println!("Updated {:?}", refs);
+                    self.toasts.push(Toast {
+                        title: "Updated".into(),
+                        body: format!("{} updates applied", refs.len()),
+                        status: Status::Success,
+                        progress: None,
+                        operation: None,
+                        ref_: None,
+                    });
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::RefreshInstalled));
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::RefreshUpdates));
+                }
+                action::Message::UpdateFailed(err) => self.show_error("Update failed", &err),
+                action::Message::Runtimes(runtimes) => {
+                    self.installed_page
+                        .update(InstalledPageMessage::Runtimes(runtimes.clone()));
+                    self.runtimes_page
+                        .update(RuntimesPageMessage::Listed(runtimes));
+                }
+                action::Message::UnusedRemoved(refs) => {
+                    println!("Removed unused {:?}", refs);
+                    self.toasts.push(Toast {
+                        title: "Cleaned up".into(),
+                        body: format!("{} unused runtimes and extensions removed", refs.len()),
+                        status: Status::Success,
+                        progress: None,
+                        operation: None,
+                        ref_: None,
+                    });
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::RefreshRuntimes));
+                }
+                action::Message::AppData(orphaned) => {
+                    self.app_data_page
+                        .update(AppDataPageMessage::Listed(orphaned));
+                }
+                action::Message::AppDataDeleted(apps) => {
+                    self.toasts.push(Toast {
+                        title: "Cleaned up".into(),
+                        body: format!("Deleted the data of {} apps", apps.len()),
+                        status: Status::Success,
+                        progress: None,
+                        operation: None,
+                        ref_: None,
+                    });
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::RefreshAppData));
+                }
+                action::Message::History((id, history)) => {
+                    self.app_view_page
+                        .update(AppViewMessage::History((id, history)));
+                }
+                action::Message::RolledBack((id, scope)) => {
+                    self.toasts.push(Toast {
+                        title: "Rolled back".into(),
+                        body: format!("{} is on the chosen version", id),
+                        status: Status::Success,
+                        progress: None,
+                        operation: None,
+                        ref_: None,
+                    });
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::RefreshInstalled));
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::LoadHistory((id, scope))));
+                }
+                action::Message::HeldChanged((id, held)) => {
+                    println!("{} held: {}", id, held);
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::RefreshInstalled));
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::RefreshUpdates));
+                }
+                action::Message::Backups((id, backups)) => {
+                    self.app_view_page
+                        .update(AppViewMessage::Backups((id, backups)));
+                }
+                action::Message::BackupCreated(backup) => {
+                    self.toasts.push(Toast {
+                        title: "Backed up".into(),
+                        body: format!("The data of {} was saved", backup.app),
+                        status: Status::Success,
+                        progress: None,
+                        operation: None,
+                        ref_: None,
+                    });
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::ListBackups(backup.app)));
+                }
+                action::Message::BackupRestored(backup) => {
+                    self.toasts.push(Toast {
+                        title: "Restored".into(),
+                        body: format!("The data of {} is back to {}", backup.app, backup.created),
+                        status: Status::Success,
+                        progress: None,
+                        operation: None,
+                        ref_: None,
+                    });
+                }
+                action::Message::ManifestExported((path, count)) => {
+                    self.toasts.push(Toast {
+                        title: "Exported".into(),
+                        body: format!("Wrote {} apps to {}", count, path.display()),
+                        status: Status::Success,
+                        progress: None,
+                        operation: None,
+                        ref_: None,
+                    });
+                }
+                action::Message::ImportPlan(plan) => {
+                    self.manifest_page
+                        .update(ManifestPageMessage::Planned(plan));
+                }
+                action::Message::Imported(refs) => {
+                    self.toasts.push(Toast {
+                        title: "Imported".into(),
+                        body: format!("Installed {} apps", refs.len()),
+                        status: Status::Success,
+                        progress: None,
+                        operation: None,
+                        ref_: None,
+                    });
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::RefreshInstalled));
+                }
+                action::Message::SyncPlan(plan) => {
+                    self.sync_page.update(SyncPageMessage::Planned(plan));
+                }
+                action::Message::Synced(steps) => {
+                    self.toasts.push(Toast {
+                        title: "In sync".into(),
+                        body: format!("Applied {} steps of the state file", steps.len()),
+                        status: Status::Success,
+                        progress: None,
+                        operation: None,
+                        ref_: None,
+                    });
+                    for refresh in [
+                        action::Action::RefreshInstalled,
+                        action::Action::RefreshUpdates,
+                        action::Action::RefreshRemotes,
+                    ] {
+                        let _ = self.action.as_mut().map(|tx| tx.start_send(refresh));
+                    }
+                }
+                action::Message::LaunchFailed((id, err)) => {
+                    self.show_error(&format!("Unable to open {}", id), &err);
+                }
+                action::Message::UninstallFailed((id, err)) => {
+                    self.show_error(&format!("Unable to uninstall {}", id), &err);
+                }
+                action::Message::Transaction(event) => self.transaction_event(event),
+                action::Message::Cancelled(operation) => self.operation_cancelled(operation),
+                action::Message::Remotes(remotes) => {
+                    self.remotes_page
+                        .update(RemotesPageMessage::Listed(remotes));
+                }
+                action::Message::RemotesChanged => {
+                    self.remotes_page.update(RemotesPageMessage::ClearForm);
+                    self.toasts.push(Toast {
+                        title: "Remotes updated".into(),
+                        body: "The package index was refreshed".into(),
+                        status: Status::Success,
+                        progress: None,
+                        operation: None,
+                        ref_: None,
+                    });
+                    let _ = self
+                        .action
+                        .as_mut()
+                        .map(|tx| tx.start_send(action::Action::RefreshRemotes));
+                    if let Some(db) = self.db.clone() {
+                        let _ = self
+                            .action
+                            .as_mut()
+                            .map(|tx| tx.start_send(action::Action::RefreshStaffPicks(db)));
+                    }
+                }
+                action::Message::Error(err) => self.show_error(err.title(), &err),
             },
             Message::Tick(now) => self.landing_page.timeline.now(now),
             Message::SearchButton => {
@@ -350,12 +1316,28 @@ impl Application for BazaarApp {
     }
 
     fn view(&self) -> iced::Element<'_, Self::Message, iced::Renderer<Self::Theme>> {
-        let content = container(match self.current_page {
+        let page = container(match self.current_page {
             Page::LandingPage => column(vec![self.landing_page.view().into()]).spacing(10.),
             Page::Installed => column(vec![self.installed_page.view().into()]).spacing(10.),
             Page::Detail => column(vec![self.app_view_page.view().into()]),
+            Page::Remotes => column(vec![self.remotes_page.view().into()]),
+            Page::LocalInstall => column(vec![self.local_install_page.view().into()]),
+            Page::Runtimes => column(vec![self.runtimes_page.view().into()]),
+            Page::AppData => column(vec![self.app_data_page.view().into()]),
+            Page::Manifest => column(vec![self.manifest_page.view().into()]),
+            Page::Sync => column(vec![self.sync_page.view().into()]),
         })
         .width(Length::Fill)
+        .height(Length::Fill);
+        let content = container(
+            column(vec![
+                self.uninstall_dialog(),
+                self.remove_unused_dialog(),
+                page.into(),
+            ])
+            .spacing(10.),
+        )
+        .width(Length::Fill)
         .height(Length::Fill)
         .padding(10.0);
 
@@ -363,7 +1345,7 @@ impl Application for BazaarApp {
         //     .timeout(self.timeout_secs)
         //     .into()
         // content.into()
-        toast::Manager::new(content, &self.toasts, Message::Close)
+        toast::Manager::new(content, &self.toasts, Message::Close, Message::Cancel)
             .timeout(self.timeout_secs)
             .into()
     }
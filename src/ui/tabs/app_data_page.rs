@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use iced::{
+    widget::{button, column, container, horizontal_rule, horizontal_space, row, scrollable, text},
+    Length,
+};
+use iced_aw::{graphics::icons::Icon, TabLabel};
+use libflatpak::glib;
+
+use crate::{
+    backend::app_data::OrphanedData,
+    ui::{
+        appearance::{self, ButtonStyle, ContainerStyle, Theme},
+        main_window::{Config, Message},
+    },
+};
+
+use super::Tab;
+
+/// Data left behind in `~/.var/app` by apps that are no longer installed.
+pub struct AppDataPage {
+    config: Config,
+
+    orphaned: Option<Arc<Vec<OrphanedData>>>,
+}
+
+pub enum AppDataPageMessage {
+    Listed(Arc<Vec<OrphanedData>>),
+}
+
+impl AppDataPage {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            orphaned: None,
+        }
+    }
+
+    pub fn update(&mut self, message: AppDataPageMessage) {
+        match message {
+            AppDataPageMessage::Listed(orphaned) => {
+                println!("Refreshed orphaned app data");
+                self.orphaned = Some(orphaned);
+            }
+        }
+    }
+
+    fn data_row(&self, data: &OrphanedData) -> iced::Element<Message, iced::Renderer<Theme>> {
+        row(vec![
+            column(vec![
+                text(&data.app).size(22).into(),
+                text(data.path.display().to_string()).size(14).into(),
+            ])
+            .width(Length::Fill)
+            .into(),
+            text(glib::format_size(data.size)).size(16).into(),
+            button(appearance::icon('\u{f1767}'))
+                .on_press(Message::DeleteAppData(vec![data.app.clone()]))
+                .style(ButtonStyle::Icon)
+                .into(),
+        ])
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center)
+        .into()
+    }
+
+    fn app_data_view(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let list: iced::Element<Message, iced::Renderer<Theme>> = match &self.orphaned {
+            Some(orphaned) if orphaned.is_empty() => {
+                text("No data left behind by uninstalled apps").into()
+            }
+            Some(orphaned) => scrollable(
+                column(orphaned.iter().map(|data| self.data_row(data)).collect()).spacing(10.0),
+            )
+            .into(),
+            None => text("Loading").size(30).into(),
+        };
+        let orphaned = self.orphaned.as_deref().map(Vec::as_slice).unwrap_or(&[]);
+        let total: u64 = orphaned.iter().map(|data| data.size).sum();
+        let mut delete_all = button(
+            row![
+                appearance::icon('\u{f1767}'),
+                text(format!("Delete all ({})", glib::format_size(total))),
+            ]
+            .spacing(10.),
+        )
+        .style(ButtonStyle::Primary)
+        .padding([10, 20]);
+        if !orphaned.is_empty() {
+            delete_all = delete_all.on_press(Message::DeleteAppData(
+                orphaned.iter().map(|data| data.app.clone()).collect(),
+            ));
+        }
+        container(
+            column(vec![
+                button(appearance::icon('\u{f030d}'))
+                    .on_press(Message::ChangePage(crate::ui::main_window::Page::Installed))
+                    .padding(10.)
+                    .style(ButtonStyle::Icon)
+                    .into(),
+                row(vec![
+                    text("Leftover App Data").size(30).into(),
+                    horizontal_space(Length::Fill).into(),
+                    delete_all.into(),
+                    button(appearance::icon('\u{eb37}'))
+                        .on_press(Message::RequestRefreshAppData)
+                        .padding(10.)
+                        .style(ButtonStyle::Icon)
+                        .into(),
+                ])
+                .into(),
+                horizontal_rule(1.).into(),
+                list,
+            ])
+            .spacing(10.0),
+        )
+        .padding(10.0)
+        .style(ContainerStyle::Default)
+        .into()
+    }
+}
+
+impl Tab for AppDataPage {
+    type Message = Message;
+
+    fn title(&self) -> String {
+        "Leftover App Data".into()
+    }
+
+    fn tab_label(&self) -> iced_aw::TabLabel {
+        TabLabel::IconText(Icon::Check.into(), self.title())
+    }
+
+    fn theme(&self) -> Theme {
+        if self.config.dark_mode {
+            Theme::Dark
+        } else {
+            Theme::Light
+        }
+    }
+
+    fn view(&self) -> iced::Element<Self::Message, iced::Renderer<Theme>> {
+        self.app_data_view()
+    }
+}
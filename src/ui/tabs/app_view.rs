@@ -1,5 +1,6 @@
 use std::{
     cell::RefCell,
+    collections::HashMap,
     path::PathBuf,
     sync::{Arc, Mutex},
 };
@@ -8,14 +9,22 @@ use iced::{
     theme,
     widget::{
         button, column, container, horizontal_rule, horizontal_space, image, row, scrollable, text,
-        Column, Container,
+        text_input, Column, Container,
     },
     Length,
 };
 use iced_aw::{graphics::icons::Icon, wrap, TabLabel};
+use libflatpak::glib;
 
 use crate::{
-    backend::flatpak_backend::Package,
+    backend::{
+        backups::Backup,
+        details::{markup_to_text, releases_since, PackageDetails},
+        flatpak_backend::{InstallationScope, OperationProgress, Package, PackageId, RefOrigin},
+        history::Commit,
+        overrides::{OverrideChange, OverrideState, Overrides, CONTEXT_KEYS},
+        permissions::{PermissionGroup, Permissions},
+    },
     ui::{
         appearance::{self, ButtonStyle, ContainerStyle, Theme},
         main_window::{Config, Message},
@@ -24,20 +33,586 @@ use crate::{
 
 use super::Tab;
 
+/// Where a screenshot of the carousel stands.
+#[derive(Debug, Clone)]
+enum ScreenshotState {
+    Loading,
+    Loaded(PathBuf),
+    /// Fetching it failed, the carousel offers to try again
+    Failed,
+}
+
 pub struct AppView {
     config: Config,
+    /// The shown app as indexed from its remote
     package: Option<Package>,
+    details: Option<PackageDetails>,
+    id: Option<PackageId>,
+    installed_apps: Arc<Mutex<RefCell<Vec<Package>>>>,
+    operations: Arc<Mutex<HashMap<PackageId, OperationProgress>>>,
+    /// Position of the screenshot shown in the carousel
+    screenshot: usize,
+    /// The screenshots fetched or being fetched, by URL
+    screenshot_states: HashMap<String, ScreenshotState>,
+    /// The branches and remotes the app is available from
+    origins: Option<Arc<Vec<RefOrigin>>>,
+    permissions: Option<Arc<Permissions>>,
+    /// Archives of the app's data, newest first
+    backups: Option<Arc<Vec<Backup>>>,
+    /// Commits of the installed ref, newest first
+    history: Option<Arc<Vec<Commit>>>,
+    /// Whether rolling back also masks the app so that it stays on the chosen commit
+    hold_after_rollback: bool,
+    overrides: Option<Arc<Overrides>>,
+    /// The installation whose overrides are edited
+    override_scope: InstallationScope,
+    env_name: String,
+    env_value: String,
+}
+
+/// Permissions offered in the overrides editor even if the app doesn't ask for them
+const COMMON_PERMISSIONS: [(&str, &str); 10] = [
+    ("shared", "network"),
+    ("filesystems", "host"),
+    ("filesystems", "home"),
+    ("filesystems", "xdg-download"),
+    ("sockets", "x11"),
+    ("sockets", "wayland"),
+    ("sockets", "pulseaudio"),
+    ("sockets", "session-bus"),
+    ("devices", "dri"),
+    ("devices", "all"),
+];
+
+#[derive(Debug, Clone)]
+pub enum AppViewMessage {
+    Open(PackageId),
+    Loaded((PackageId, Option<Package>, Option<PackageDetails>)),
+    ScreenshotLoaded((String, PathBuf)),
+    ScreenshotFailed(String),
+    /// Forgets a failed screenshot so that it is fetched again
+    RetryScreenshot(String),
+    ShowScreenshot(usize),
+    Origins((PackageId, Arc<Vec<RefOrigin>>)),
+    Permissions((PackageId, Arc<Permissions>)),
+    Backups((PackageId, Arc<Vec<Backup>>)),
+    History((PackageId, Arc<Vec<Commit>>)),
+    HoldAfterRollback,
+    Overrides((PackageId, Arc<Overrides>)),
+    OverrideScope(InstallationScope),
+    EnvNameChanged(String),
+    EnvValueChanged(String),
 }
 
 impl AppView {
-    pub fn new(config: Config) -> Self {
+    pub fn new(
+        config: Config,
+        operations: Arc<Mutex<HashMap<PackageId, OperationProgress>>>,
+        installed_apps: Arc<Mutex<RefCell<Vec<Package>>>>,
+    ) -> Self {
         Self {
             config,
             package: None,
+            details: None,
+            id: None,
+            installed_apps,
+            operations,
+            screenshot: 0,
+            screenshot_states: HashMap::new(),
+            origins: None,
+            permissions: None,
+            backups: None,
+            history: None,
+            hold_after_rollback: false,
+            overrides: None,
+            override_scope: Default::default(),
+            env_name: Default::default(),
+            env_value: Default::default(),
+        }
+    }
+
+    /// The app and installation the overrides editor currently works on.
+    pub fn override_target(&self) -> Option<(PackageId, InstallationScope)> {
+        self.id.clone().map(|id| (id, self.override_scope.clone()))
+    }
+
+    pub fn update(&mut self, message: AppViewMessage) {
+        match message {
+            AppViewMessage::Open(id) => {
+                self.id = Some(id);
+                self.package = None;
+                self.details = None;
+                self.screenshot = 0;
+                self.origins = None;
+                self.permissions = None;
+                self.backups = None;
+                self.history = None;
+                self.overrides = None;
+            }
+            AppViewMessage::Loaded((id, package, details)) => {
+                if self.id.as_ref() == Some(&id) {
+                    self.screenshot = details
+                        .as_ref()
+                        .and_then(|d| d.screenshots.iter().position(|s| s.is_default))
+                        .unwrap_or(0);
+                    self.package = package;
+                    self.details = details;
+                }
+            }
+            AppViewMessage::ScreenshotLoaded((url, path)) => {
+                self.screenshot_states
+                    .insert(url, ScreenshotState::Loaded(path));
+            }
+            AppViewMessage::ScreenshotFailed(url) => {
+                self.screenshot_states.insert(url, ScreenshotState::Failed);
+            }
+            AppViewMessage::RetryScreenshot(url) => {
+                self.screenshot_states.remove(&url);
+            }
+            AppViewMessage::ShowScreenshot(index) => self.screenshot = index,
+            AppViewMessage::Origins((id, origins)) => {
+                // Ignore a late answer for an app that is no longer shown
+                if self.id.as_ref() == Some(&id) {
+                    self.origins = Some(origins);
+                }
+            }
+            AppViewMessage::Permissions((id, permissions)) => {
+                if self.id.as_ref() == Some(&id) {
+                    self.permissions = Some(permissions);
+                }
+            }
+            AppViewMessage::Backups((id, backups)) => {
+                if self.id.as_ref() == Some(&id) {
+                    self.backups = Some(backups);
+                }
+            }
+            AppViewMessage::History((id, history)) => {
+                if self.id.as_ref() == Some(&id) {
+                    self.history = Some(history);
+                }
+            }
+            AppViewMessage::HoldAfterRollback => {
+                self.hold_after_rollback = !self.hold_after_rollback
+            }
+            AppViewMessage::Overrides((id, overrides)) => {
+                if self.id.as_ref() == Some(&id) && overrides.scope == self.override_scope {
+                    self.overrides = Some(overrides);
+                }
+            }
+            AppViewMessage::OverrideScope(scope) => {
+                self.override_scope = scope;
+                self.overrides = None;
+            }
+            AppViewMessage::EnvNameChanged(name) => self.env_name = name,
+            AppViewMessage::EnvValueChanged(value) => self.env_value = value,
+        }
+    }
+
+    /// The shown screenshot and the one after it unless they are cached or being fetched
+    /// already, marked as being fetched.
+    pub fn screenshots_to_fetch(&mut self) -> Vec<String> {
+        let Some(details) = &self.details else {
+            return vec![];
+        };
+        let count = details.screenshots.len();
+        let mut urls = vec![];
+        for index in [self.screenshot, self.screenshot + 1] {
+            if count == 0 {
+                break;
+            }
+            let url = &details.screenshots[index % count].url;
+            if !self.screenshot_states.contains_key(url) && !urls.contains(url) {
+                urls.push(url.clone());
+            }
+        }
+        for url in &urls {
+            self.screenshot_states
+                .insert(url.clone(), ScreenshotState::Loading);
         }
+        urls
+    }
+
+    fn origin_row(
+        &self,
+        origin: &RefOrigin,
+        installed: bool,
+    ) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let action: iced::Element<Message, iced::Renderer<Theme>> = if origin.installed {
+            text("Installed").size(16).into()
+        } else if installed {
+            button(text("Switch"))
+                .style(ButtonStyle::Secondary)
+                .padding([6, 14])
+                .on_press(Message::SwitchOrigin(origin.clone()))
+                .into()
+        } else {
+            button(text("Install"))
+                .style(ButtonStyle::Primary)
+                .padding([6, 14])
+                .on_press(Message::InstallOrigin(origin.clone()))
+                .into()
+        };
+        row(vec![
+            column(vec![
+                text(&origin.branch).size(20).into(),
+                text(format!("{} ({})", origin.remote, origin.scope.to_string()))
+                    .size(14)
+                    .into(),
+            ])
+            .width(Length::Fill)
+            .into(),
+            action,
+        ])
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center)
+        .into()
     }
 
-    // pub fn update(&mut self, message: InstalledPageMessage) {}
+    fn origins_view(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let rows = match &self.origins {
+            Some(origins) => {
+                let installed = origins.iter().any(|o| o.installed);
+                origins
+                    .iter()
+                    .map(|origin| self.origin_row(origin, installed))
+                    .collect()
+            }
+            None => vec![text("Loading").size(16).into()],
+        };
+        column(vec![
+            text("Versions").size(24).into(),
+            horizontal_rule(1.).into(),
+            column(rows).spacing(10.0).into(),
+        ])
+        .spacing(10.0)
+        .into()
+    }
+
+    fn history_view(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let Some((id, scope)) = self.installed_target() else {
+            return column(vec![]).into();
+        };
+        let mut section = column(vec![
+            row(vec![
+                text("Version history").size(24).into(),
+                horizontal_space(Length::Fill).into(),
+                button(text("Hold at the chosen version"))
+                    .style(if self.hold_after_rollback {
+                        ButtonStyle::Primary
+                    } else {
+                        ButtonStyle::Secondary
+                    })
+                    .padding([6, 14])
+                    .on_press(Message::AppView(AppViewMessage::HoldAfterRollback))
+                    .into(),
+            ])
+            .align_items(iced::Alignment::Center)
+            .into(),
+            horizontal_rule(1.).into(),
+        ])
+        .spacing(10.0);
+        let Some(history) = &self.history else {
+            return section.push(text("Loading").size(16)).into();
+        };
+        for commit in history.iter() {
+            let action: iced::Element<Message, iced::Renderer<Theme>> = if commit.installed {
+                text("Installed").size(14).into()
+            } else {
+                button(text("Roll back"))
+                    .style(ButtonStyle::Secondary)
+                    .padding([6, 14])
+                    .on_press(Message::Rollback((
+                        id.clone(),
+                        scope.clone(),
+                        commit.commit.clone(),
+                        self.hold_after_rollback,
+                    )))
+                    .into()
+            };
+            section = section.push(
+                row(vec![
+                    column(vec![
+                        text(&commit.subject).size(16).into(),
+                        text(format!(
+                            "{} · {}",
+                            &commit.commit[..commit.commit.len().min(12)],
+                            commit.date
+                        ))
+                        .size(14)
+                        .into(),
+                    ])
+                    .width(Length::Fill)
+                    .into(),
+                    action,
+                ])
+                .spacing(10.0)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+        section.into()
+    }
+
+    fn backups_view(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let Some(id) = &self.id else {
+            return column(vec![]).into();
+        };
+        let mut section = column(vec![
+            row(vec![
+                text("Data Backups").size(24).into(),
+                horizontal_space(Length::Fill).into(),
+                button(text("Back up now"))
+                    .style(ButtonStyle::Secondary)
+                    .padding([6, 14])
+                    .on_press(Message::CreateBackup(id.clone()))
+                    .into(),
+            ])
+            .align_items(iced::Alignment::Center)
+            .into(),
+            horizontal_rule(1.).into(),
+        ])
+        .spacing(10.0);
+        match &self.backups {
+            None => section = section.push(text("Loading").size(16)),
+            Some(backups) if backups.is_empty() => {
+                section = section.push(text("No backups of the app's data yet").size(16))
+            }
+            Some(backups) => {
+                for backup in backups.iter() {
+                    section = section.push(
+                        row(vec![
+                            text(&backup.created).size(16).width(Length::Fill).into(),
+                            text(glib::format_size(backup.size)).size(14).into(),
+                            button(text("Restore"))
+                                .style(ButtonStyle::Secondary)
+                                .padding([6, 14])
+                                .on_press(Message::RestoreBackup(backup.clone()))
+                                .into(),
+                        ])
+                        .spacing(10.0)
+                        .align_items(iced::Alignment::Center),
+                    );
+                }
+            }
+        }
+        section.into()
+    }
+
+    fn permissions_view(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let mut groups = column(vec![
+            text("Permissions").size(24).into(),
+            horizontal_rule(1.).into(),
+        ])
+        .spacing(10.0);
+        let Some(permissions) = &self.permissions else {
+            return groups.push(text("Loading").size(16)).into();
+        };
+        if permissions.entries.is_empty() {
+            return groups
+                .push(text("No special permissions, fully sandboxed").size(16))
+                .into();
+        }
+        for group in PermissionGroup::ALL {
+            let entries: Vec<_> = permissions
+                .group(group)
+                .map(|permission| {
+                    row(vec![
+                        text(&permission.explanation)
+                            .size(16)
+                            .width(Length::Fill)
+                            .into(),
+                        text(&permission.value).size(14).into(),
+                    ])
+                    .spacing(10.0)
+                    .into()
+                })
+                .collect();
+            if !entries.is_empty() {
+                groups = groups
+                    .push(text(group.to_string()).size(20))
+                    .push(column(entries).spacing(4.0));
+            }
+        }
+        groups.into()
+    }
+
+    /// Flips `value` of `key` from what the app currently gets, dropping the override once it
+    /// matches the app's own default again.
+    fn override_toggle<'a>(
+        &self,
+        overrides: &Overrides,
+        key: &str,
+        value: &str,
+    ) -> iced::Element<'a, Message, iced::Renderer<Theme>> {
+        let default = self
+            .permissions
+            .as_ref()
+            .map_or(false, |p| p.is_granted(key, value));
+        let state = overrides.state(key, value);
+        let granted = match state {
+            Some(OverrideState::Granted) => true,
+            Some(OverrideState::Revoked) => false,
+            None => default,
+        };
+        let new_state = if !granted == default {
+            None
+        } else if !granted {
+            Some(OverrideState::Granted)
+        } else {
+            Some(OverrideState::Revoked)
+        };
+        let label = if state.is_some() {
+            format!("{} *", value)
+        } else {
+            value.to_string()
+        };
+        let toggle = button(text(label))
+            .style(if granted {
+                ButtonStyle::Primary
+            } else {
+                ButtonStyle::Secondary
+            })
+            .padding([6, 14]);
+        if !overrides.writable {
+            return toggle.into();
+        }
+        toggle
+            .on_press(Message::SetOverride(OverrideChange::Context {
+                key: key.to_string(),
+                value: value.to_string(),
+                state: new_state,
+            }))
+            .into()
+    }
+
+    fn overrides_view(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let scope_button = |scope: InstallationScope| {
+            button(text(scope.to_string()))
+                .style(if self.override_scope == scope {
+                    ButtonStyle::Primary
+                } else {
+                    ButtonStyle::Secondary
+                })
+                .padding([6, 14])
+                .on_press(Message::AppView(AppViewMessage::OverrideScope(scope)))
+        };
+        let writable = self.overrides.as_ref().map_or(false, |o| o.writable);
+        let mut reset = button(text("Reset to defaults"))
+            .style(ButtonStyle::Secondary)
+            .padding([6, 14]);
+        if writable {
+            reset = reset.on_press(Message::SetOverride(OverrideChange::Reset));
+        }
+        let mut editor = column(vec![
+            row(vec![
+                text("Overrides").size(24).into(),
+                horizontal_space(Length::Fill).into(),
+                scope_button(InstallationScope::User).into(),
+                scope_button(InstallationScope::System).into(),
+                reset.into(),
+            ])
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center)
+            .into(),
+            horizontal_rule(1.).into(),
+        ])
+        .spacing(10.0);
+        let Some(overrides) = &self.overrides else {
+            return editor.push(text("Loading").size(16)).into();
+        };
+        if !writable {
+            editor = editor.push(
+                text(format!(
+                    "Changing these overrides needs administrator rights, use `sudo flatpak override {} {}`",
+                    overrides.scope.cli_option(),
+                    overrides.app
+                ))
+                .size(16),
+            );
+        }
+
+        for key in CONTEXT_KEYS {
+            let mut values: Vec<String> = COMMON_PERMISSIONS
+                .iter()
+                .filter(|(k, _)| *k == key)
+                .map(|(_, v)| v.to_string())
+                .collect();
+            let requested = self.permissions.iter().flat_map(|p| {
+                p.entries
+                    .iter()
+                    .filter(|e| e.key == key)
+                    .map(|e| e.value.clone())
+            });
+            let overridden = overrides
+                .context
+                .iter()
+                .filter(|(k, _)| k == key)
+                .map(|(_, v)| v.clone());
+            for value in requested.chain(overridden) {
+                let value = value.trim_start_matches('!').to_string();
+                if !values.contains(&value) {
+                    values.push(value);
+                }
+            }
+            let toggles = values
+                .iter()
+                .map(|value| self.override_toggle(overrides, key, value))
+                .collect();
+            editor = editor.push(text(key).size(18)).push(
+                wrap::Wrap::with_elements(toggles)
+                    .spacing(10.0)
+                    .line_spacing(10.0),
+            );
+        }
+
+        editor = editor.push(text("Environment").size(18));
+        for (name, value) in &overrides.environment {
+            let mut unset = button(appearance::icon('\u{f1767}')).style(ButtonStyle::Icon);
+            if writable {
+                unset = unset.on_press(Message::SetOverride(OverrideChange::Environment {
+                    name: name.clone(),
+                    value: None,
+                }));
+            }
+            editor = editor.push(
+                row(vec![
+                    text(format!("{}={}", name, value))
+                        .size(16)
+                        .width(Length::Fill)
+                        .into(),
+                    unset.into(),
+                ])
+                .align_items(iced::Alignment::Center),
+            );
+        }
+        let mut set_env = button(text("Set"))
+            .style(ButtonStyle::Primary)
+            .padding([6, 14]);
+        if writable && !self.env_name.is_empty() {
+            set_env = set_env.on_press(Message::SetOverride(OverrideChange::Environment {
+                name: self.env_name.clone(),
+                value: Some(self.env_value.clone()),
+            }));
+        }
+        editor
+            .push(
+                row(vec![
+                    text_input("Variable", &self.env_name)
+                        .on_input(|name| Message::AppView(AppViewMessage::EnvNameChanged(name)))
+                        .padding([4.0, 12.0, 4.0, 12.0])
+                        .width(Length::Fixed(200.))
+                        .into(),
+                    text_input("Value", &self.env_value)
+                        .on_input(|value| Message::AppView(AppViewMessage::EnvValueChanged(value)))
+                        .padding([4.0, 12.0, 4.0, 12.0])
+                        .width(Length::Fill)
+                        .into(),
+                    set_env.into(),
+                ])
+                .spacing(10.0)
+                .align_items(iced::Alignment::Center),
+            )
+            .into()
+    }
 
     fn app_icon<'a>(&self, width: u16) -> iced::Element<Message, iced::Renderer<Theme>> {
         let path = if let Some(package) = &self.package {
@@ -87,6 +662,22 @@ impl AppView {
     //     .height(Length::Shrink)
     //     .into()
     // }
+    /// The shown app and the installation it is installed in, if it is installed.
+    pub fn installed_target(&self) -> Option<(PackageId, InstallationScope)> {
+        self.installed_package().map(|p| (p.name, p.scope))
+    }
+
+    /// The installed copy of the shown app, if any
+    fn installed_package(&self) -> Option<Package> {
+        let id = self.id.as_ref()?;
+        self.installed_apps
+            .try_lock()
+            .ok()?
+            .borrow()
+            .iter()
+            .find(|p| &p.name == id)
+            .cloned()
+    }
     fn header(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
         // <space> <content> <space>
         // and content lookslike:
@@ -95,33 +686,276 @@ impl AppView {
         //          <source>
         //<size> <version> <rating>
         //
-        let app_dashboard = row(vec![self.app_icon(128)]);
+        let back = button(appearance::icon('\u{f030d}'))
+            .on_press(Message::ChangePage(
+                crate::ui::main_window::Page::LandingPage,
+            ))
+            .padding(10.)
+            .style(ButtonStyle::Icon);
+        let Some(id) = &self.id else {
+            return container(Column::new().push(back)).into();
+        };
+        let installed = self.installed_package();
+        let package = self.package.as_ref().or(installed.as_ref());
+        let details = self.details.as_ref();
+
+        let name = package
+            .and_then(|p| p.pretty_name.clone())
+            .unwrap_or(id.clone());
+        let developer = details.and_then(|d| d.developer.clone());
+        let source = package.map(|p| {
+            format!(
+                "{} {}",
+                p.remote.clone().unwrap_or_default(),
+                p.branch.clone().unwrap_or_default()
+            )
+        });
+        let version = installed
+            .as_ref()
+            .and_then(|p| p.version.clone())
+            .or(details.and_then(|d| d.releases.first().map(|r| r.version.clone())));
+        let mut facts = vec![];
+        if let Some(version) = version {
+            facts.push(format!("Version {}", version));
+        }
+        if let Some(sizes) = installed
+            .as_ref()
+            .or(package)
+            .and_then(|p| p.size_summary())
+        {
+            facts.push(sizes);
+        }
+        if let Some(license) = details.and_then(|d| d.license.clone()) {
+            facts.push(license);
+        }
+        if let Some(installed) = &installed {
+            facts.push(format!("Installed ({})", installed.scope.to_string()));
+        }
+
+        let action: iced::Element<Message, iced::Renderer<Theme>> = match &installed {
+            Some(installed) => row(vec![
+                button(row![appearance::icon('\u{f03cc}'), text("Open")].spacing(10.))
+                    .style(ButtonStyle::Primary)
+                    .padding([10, 20])
+                    .on_press(Message::Launch(id.clone()))
+                    .into(),
+                button(text(if installed.held {
+                    "Unpin"
+                } else {
+                    "Pin this version"
+                }))
+                .style(ButtonStyle::Secondary)
+                .padding([10, 20])
+                .on_press(Message::SetHeld((
+                    id.clone(),
+                    installed.scope.clone(),
+                    !installed.held,
+                )))
+                .into(),
+                button(row![appearance::icon('\u{f1767}'), text("Uninstall")].spacing(10.))
+                    .style(ButtonStyle::Secondary)
+                    .padding([10, 20])
+                    .on_press(Message::Uninstall((id.clone(), installed.scope.clone())))
+                    .into(),
+            ])
+            .spacing(10.0)
+            .into(),
+            None => button(row![appearance::icon('\u{f498}'), text("Install")].spacing(10.))
+                .style(ButtonStyle::Primary)
+                .padding([10, 20])
+                .on_press(Message::Install(id.clone()))
+                .into(),
+        };
+
+        let app_dashboard = row(vec![
+            self.app_icon(128),
+            column(vec![
+                text(name).size(34).into(),
+                text(developer.unwrap_or_default()).size(18).into(),
+                text(source.unwrap_or_default()).size(14).into(),
+                text(facts.join("  ·  ")).size(14).into(),
+                operation_progress(&self.operations, id),
+            ])
+            .spacing(6.0)
+            .width(Length::Fill)
+            .into(),
+            action,
+        ])
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center);
 
         container(
             Column::new()
-                .push(
-                    button(appearance::icon('\u{f030d}'))
-                        .on_press(Message::ChangePage(
-                            crate::ui::main_window::Page::LandingPage,
-                        ))
-                        .padding(10.)
-                        .style(ButtonStyle::Icon),
-                )
+                .push(back)
                 .push(horizontal_space(Length::Fill))
                 .push(app_dashboard)
                 .push(horizontal_space(Length::Fill)),
         )
         .into()
     }
+
+    fn links(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let mut links = row(vec![]).spacing(10.0);
+        if let Some(details) = &self.details {
+            for (label, url) in [
+                ("Website", &details.homepage),
+                ("Report an issue", &details.bugtracker),
+                ("Donate", &details.donation),
+            ] {
+                if let Some(url) = url {
+                    links = links.push(
+                        button(text(label))
+                            .style(ButtonStyle::Secondary)
+                            .padding([6, 14])
+                            .on_press(Message::OpenUrl(url.clone())),
+                    );
+                }
+            }
+        }
+        links.into()
+    }
+
+    fn screenshots_view(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let screenshots = match &self.details {
+            Some(details) if !details.screenshots.is_empty() => &details.screenshots,
+            _ => return column(vec![]).into(),
+        };
+        let count = screenshots.len();
+        let index = self.screenshot.min(count - 1);
+        let shown = &screenshots[index];
+        let picture: iced::Element<Message, iced::Renderer<Theme>> =
+            match self.screenshot_states.get(&shown.url) {
+                Some(ScreenshotState::Loaded(path)) => image(path.clone())
+                    .content_fit(iced::ContentFit::Contain)
+                    .width(Length::Fill)
+                    .height(Length::Fixed(400.))
+                    .into(),
+                Some(ScreenshotState::Failed) => container(
+                    column(vec![
+                        text("Unable to load the screenshot").into(),
+                        button(text("Retry"))
+                            .style(ButtonStyle::Secondary)
+                            .padding([6, 14])
+                            .on_press(Message::AppView(AppViewMessage::RetryScreenshot(
+                                shown.url.clone(),
+                            )))
+                            .into(),
+                    ])
+                    .spacing(10.0)
+                    .align_items(iced::Alignment::Center),
+                )
+                .width(Length::Fill)
+                .height(Length::Fixed(400.))
+                .center_x()
+                .center_y()
+                .into(),
+                Some(ScreenshotState::Loading) | None => container(text("Loading screenshot..."))
+                    .width(Length::Fill)
+                    .height(Length::Fixed(400.))
+                    .center_x()
+                    .center_y()
+                    .into(),
+            };
+        let step = |icon: char, to: usize| {
+            let button = button(appearance::icon(icon)).style(ButtonStyle::Icon);
+            if count > 1 {
+                button.on_press(Message::AppView(AppViewMessage::ShowScreenshot(to)))
+            } else {
+                button
+            }
+        };
+        column(vec![
+            row(vec![
+                step('\u{f0141}', (index + count - 1) % count).into(),
+                picture,
+                step('\u{f0142}', (index + 1) % count).into(),
+            ])
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center)
+            .into(),
+            row(vec![
+                text(shown.caption.clone().unwrap_or_default())
+                    .size(14)
+                    .into(),
+                horizontal_space(Length::Fill).into(),
+                text(format!("{} / {}", index + 1, count)).size(14).into(),
+            ])
+            .into(),
+        ])
+        .spacing(6.0)
+        .into()
+    }
+
+    /// The releases an update of the installed app brings, or the latest one if not installed
+    fn changelog_view(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let Some(details) = &self.details else {
+            return column(vec![]).into();
+        };
+        let installed = self.installed_package();
+        let installed_version = installed.as_ref().and_then(|p| p.version.clone());
+        let releases = releases_since(&details.releases, installed_version.as_deref());
+        if releases.is_empty() {
+            return column(vec![]).into();
+        }
+        let title = match &installed_version {
+            Some(version) => format!("What's new since {}", version),
+            None => "Latest release".to_string(),
+        };
+        let mut changelog = column(vec![text(title).size(24).into()]).spacing(10.0);
+        for release in releases {
+            let heading = match &release.date {
+                Some(date) => format!("{} ({})", release.version, date),
+                None => release.version.clone(),
+            };
+            changelog = changelog.push(text(heading).size(18)).push(
+                text(
+                    release
+                        .description
+                        .as_deref()
+                        .map(markup_to_text)
+                        .unwrap_or("No release notes".into()),
+                )
+                .size(14),
+            );
+        }
+        changelog.into()
+    }
+
+    fn description(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let summary = self.package.as_ref().and_then(|p| p.summary.clone());
+        let description = self
+            .package
+            .as_ref()
+            .and_then(|p| p.description.as_deref().map(markup_to_text));
+        column(vec![
+            text(summary.unwrap_or_default()).size(22).into(),
+            text(description.unwrap_or_default()).size(16).into(),
+            self.links(),
+        ])
+        .spacing(10.0)
+        .into()
+    }
+
     fn app_view(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
         container(
             column(vec![
                 self.header(),
                 scrollable(
                     container(
-                        text("Testing"), // wrap::Wrap::with_elements(apps)
-                                         //     .spacing(10.0)
-                                         //     .line_spacing(10.0),
+                        column(vec![
+                            self.screenshots_view(),
+                            self.description(),
+                            self.changelog_view(),
+                            self.origins_view(),
+                            self.permissions_view(),
+                            self.history_view(),
+                            self.overrides_view(),
+                            self.backups_view(),
+                        ])
+                        .spacing(20.0),
+                        // wrap::Wrap::with_elements(apps)
+                        //     .spacing(10.0)
+                        //     .line_spacing(10.0),
                     )
                     .width(Length::Fill)
                     .center_x(),
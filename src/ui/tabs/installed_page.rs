@@ -1,5 +1,6 @@
 use std::{
     cell::RefCell,
+    collections::HashMap,
     path::PathBuf,
     sync::{Arc, Mutex},
 };
@@ -13,12 +14,18 @@ use iced::{
     Length,
 };
 use iced_aw::{graphics::icons::Icon, wrap, TabLabel};
+use libflatpak::glib;
 
 use crate::{
-    backend::flatpak_backend::Package,
+    backend::{
+        details::{markup_to_text, Release},
+        eol::EolNotice,
+        flatpak_backend::{OperationProgress, Package, PackageId},
+        runtimes::RuntimeInfo,
+    },
     ui::{
         appearance::{self, ButtonStyle, ContainerStyle, Theme},
-        custom_widgets::appcard::AppCard,
+        custom_widgets::{appcard::AppCard, operation_progress::operation_progress},
         main_window::{Config, Message},
     },
 };
@@ -30,19 +37,36 @@ pub struct InstalledPage {
 
     pub installed_apps: Arc<Mutex<RefCell<Vec<Package>>>>,
     pub Updatable_apps: Arc<Mutex<RefCell<Vec<Package>>>>,
+    operations: Arc<Mutex<HashMap<PackageId, OperationProgress>>>,
+    /// What the pending updates bring, newest release first
+    changelogs: Arc<HashMap<PackageId, Vec<Release>>>,
+    /// Counted in the disk usage next to the apps
+    runtimes: Arc<Vec<RuntimeInfo>>,
+    /// Apps that are end-of-life or run on an end-of-life runtime
+    eol: Arc<Vec<EolNotice>>,
 }
 
 pub enum InstalledPageMessage {
     Installed(Arc<Vec<Package>>),
     Updates(Arc<Vec<Package>>),
+    Changelogs(Arc<HashMap<PackageId, Vec<Release>>>),
+    Runtimes(Arc<Vec<RuntimeInfo>>),
+    Eol(Arc<Vec<EolNotice>>),
 }
 
 impl InstalledPage {
-    pub fn new(config: Config) -> Self {
+    pub fn new(
+        config: Config,
+        operations: Arc<Mutex<HashMap<PackageId, OperationProgress>>>,
+    ) -> Self {
         Self {
             config,
+            operations,
             installed_apps: Default::default(),
             Updatable_apps: Default::default(),
+            changelogs: Default::default(),
+            runtimes: Default::default(),
+            eol: Default::default(),
         }
     }
 
@@ -56,6 +80,9 @@ impl InstalledPage {
                 println!("Refreshed updates");
                 *self.Updatable_apps.lock().unwrap().borrow_mut() = Arc::try_unwrap(apps).unwrap();
             }
+            InstalledPageMessage::Changelogs(changelogs) => self.changelogs = changelogs,
+            InstalledPageMessage::Runtimes(runtimes) => self.runtimes = runtimes,
+            InstalledPageMessage::Eol(eol) => self.eol = eol,
         }
     }
 
@@ -76,8 +103,121 @@ impl InstalledPage {
         .padding(10)
         .center_x()
     }
+    /// The versions an update brings and the notes of the newest one
+    fn changelog(&self, package: &Package) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let Some(releases) = self.changelogs.get(&package.name).filter(|r| !r.is_empty()) else {
+            return column(vec![]).into();
+        };
+        let versions: Vec<&str> = releases.iter().map(|r| r.version.as_str()).collect();
+        let mut notes = releases[0]
+            .description
+            .as_deref()
+            .map(markup_to_text)
+            .unwrap_or_default();
+        if notes.chars().count() > 160 {
+            notes = format!("{}...", notes.chars().take(160).collect::<String>());
+        }
+        column(vec![
+            text(format!(
+                "{} → {}",
+                package.version.clone().unwrap_or("?".into()),
+                versions.join(", ")
+            ))
+            .size(14)
+            .into(),
+            text(notes).size(14).width(Length::Fixed(250.)).into(),
+        ])
+        .spacing(4.0)
+        .into()
+    }
+
+    /// A warning per end-of-life notice of the app, with the migration if it was renamed
+    fn eol_banner(&self, package: &Package) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let notices = self
+            .eol
+            .iter()
+            .filter(|notice| notice.app == package.name && notice.scope == package.scope);
+        column(
+            notices
+                .map(|notice| {
+                    let title = if notice.is_runtime() {
+                        format!("Runtime {} is end-of-life", notice.eol_ref)
+                    } else {
+                        "End-of-life".to_string()
+                    };
+                    let mut banner = column(vec![
+                        row(vec![
+                            appearance::icon('\u{f0026}').into(),
+                            text(title).size(14).into(),
+                        ])
+                        .spacing(6.0)
+                        .align_items(iced::Alignment::Center)
+                        .into(),
+                        text(&notice.reason)
+                            .size(14)
+                            .width(Length::Fixed(250.))
+                            .into(),
+                    ])
+                    .spacing(4.0);
+                    if let Some(name) = notice.rebase_name() {
+                        banner = banner.push(
+                            button(text(format!("Migrate to {}", name)).size(14))
+                                .on_press(Message::MigrateEol(notice.clone()))
+                                .padding([4, 8])
+                                .style(ButtonStyle::Primary),
+                        );
+                    }
+                    container(banner)
+                        .padding(8.0)
+                        .style(ContainerStyle::Section)
+                        .into()
+                })
+                .collect(),
+        )
+        .spacing(6.0)
+        .into()
+    }
 
-    fn app_card(&self, package: &Package) -> iced::Element<Message, iced::Renderer<Theme>> {
+    fn app_card(
+        &self,
+        package: &Package,
+        updatable: bool,
+    ) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let mut buttons = column(vec![]).spacing(10.0).push(
+            button(appearance::icon('\u{f03cc}'))
+                .on_press(Message::Launch(package.name.clone()))
+                .style(ButtonStyle::Icon),
+        );
+        if package.held {
+            // Unpinning lets the next update check list it as a plain update
+            buttons = buttons.push(
+                button(text("Unpin").size(14))
+                    .on_press(Message::SetHeld((
+                        package.name.clone(),
+                        package.scope.clone(),
+                        false,
+                    )))
+                    .padding([4, 8])
+                    .style(ButtonStyle::Secondary),
+            );
+        } else if updatable {
+            buttons = buttons.push(
+                button(appearance::icon('\u{f06b0}'))
+                    .on_press(Message::Update((
+                        package.name.clone(),
+                        package.scope.clone(),
+                    )))
+                    .style(ButtonStyle::Icon),
+            );
+        }
+        buttons = buttons.push(
+            button(appearance::icon('\u{f1767}'))
+                .on_press(Message::Uninstall((
+                    package.name.clone(),
+                    package.scope.clone(),
+                )))
+                .style(ButtonStyle::Icon),
+        );
         AppCard::new(
             container(row(vec![
                 self.app_icon(64, &package.icon_path).into(),
@@ -92,13 +232,25 @@ impl InstalledPage {
                         // .style(theme::Text::Color(self.style_sheet().app_card_text_color))
                         .size(18)
                         .into(),
+                    text(package.size_summary().unwrap_or_default())
+                        .size(14)
+                        .into(),
+                    if package.held {
+                        text("Held, updates are not applied").size(14).into()
+                    } else {
+                        column(vec![]).into()
+                    },
+                    if updatable {
+                        self.changelog(package)
+                    } else {
+                        column(vec![]).into()
+                    },
+                    self.eol_banner(package),
+                    operation_progress(&self.operations, &package.name),
                 ])
                 .width(Length::Shrink)
                 .into(),
-                button(appearance::icon('\u{f1767}'))
-                    .on_press(Message::Uninstall(package.name.clone()))
-                    .style(ButtonStyle::Icon)
-                    .into(),
+                buttons.into(),
             ])),
             package.name.clone(),
             Message::Detail,
@@ -110,6 +262,73 @@ impl InstalledPage {
         .into()
     }
 
+    /// Everything installed with what it takes on disk, largest first
+    fn disk_usage_view(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let mut usage: Vec<(String, String, u64)> = vec![];
+        if let Ok(installed_apps) = self.installed_apps.try_lock() {
+            for package in installed_apps.borrow().iter() {
+                usage.push((
+                    package.pretty_name.clone().unwrap_or(package.name.clone()),
+                    format!("App ({})", package.scope.to_string()),
+                    package.installed_size.unwrap_or(0),
+                ));
+            }
+        }
+        for runtime in self.runtimes.iter() {
+            usage.push((
+                format!(
+                    "{} {}",
+                    runtime.name,
+                    runtime.branch.clone().unwrap_or_default()
+                ),
+                format!(
+                    "{} ({})",
+                    runtime.kind.to_string(),
+                    runtime.scope.to_string()
+                ),
+                runtime.installed_size,
+            ));
+        }
+        usage.sort_by(|a, b| b.2.cmp(&a.2));
+        let total: u64 = usage.iter().map(|(_, _, size)| size).sum();
+        column(vec![
+            row(vec![
+                text("Disk Usage").size(30).into(),
+                horizontal_space(Length::Fill).into(),
+                text(format!("{} in total", glib::format_size(total)))
+                    .size(18)
+                    .into(),
+            ])
+            .align_items(iced::Alignment::Center)
+            .into(),
+            horizontal_rule(1.).into(),
+            scrollable(
+                column(
+                    usage
+                        .into_iter()
+                        .map(|(name, kind, size)| {
+                            row(vec![
+                                text(name).width(Length::Fill).into(),
+                                text(kind).size(14).width(Length::Fixed(200.)).into(),
+                                text(glib::format_size(size))
+                                    .width(Length::Fixed(100.))
+                                    .into(),
+                            ])
+                            .spacing(10.0)
+                            .into()
+                        })
+                        .collect(),
+                )
+                .spacing(6.0)
+                .padding([0, 20, 0, 0]),
+            )
+            .height(Length::Fixed(250.))
+            .into(),
+        ])
+        .spacing(10.0)
+        .into()
+    }
+
     fn installed_apps_view(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
         container(
             column(vec![
@@ -123,14 +342,22 @@ impl InstalledPage {
                 if let Ok(udpatable_apps) = self.Updatable_apps.try_lock() {
                     let mut apps = vec![];
                     for package in udpatable_apps.borrow().iter() {
-                        apps.push(self.app_card(&package));
+                        apps.push(self.app_card(&package, true));
                     }
                     column(vec![
                         row(vec![
                             text("Updates").size(30).into(),
                             horizontal_space(Length::Fill).into(),
+                            button(
+                                row![appearance::icon('\u{f06b0}'), text("Update all"),]
+                                    .spacing(10.),
+                            )
+                            .style(ButtonStyle::Primary)
+                            .padding([10, 20])
+                            .on_press(Message::UpdateAll)
+                            .into(),
                             button(appearance::icon('\u{eb37}'))
-                                .on_press(Message::RequestRefreshInstalledApps)
+                                .on_press(Message::RequestRefreshUpdates)
                                 .padding(10.)
                                 .style(ButtonStyle::Icon)
                                 .into(),
@@ -161,12 +388,38 @@ impl InstalledPage {
                 if let Ok(installed_apps) = self.installed_apps.try_lock() {
                     let mut apps = vec![];
                     for package in installed_apps.borrow().iter() {
-                        apps.push(self.app_card(&package));
+                        apps.push(self.app_card(&package, false));
                     }
                     column(vec![
                         row(vec![
                             text("Installed Apps").size(30).into(),
                             horizontal_space(Length::Fill).into(),
+                            button(text("Sync"))
+                                .style(ButtonStyle::Secondary)
+                                .padding([10, 20])
+                                .on_press(Message::ChangePage(crate::ui::main_window::Page::Sync))
+                                .into(),
+                            button(text("Import & Export"))
+                                .style(ButtonStyle::Secondary)
+                                .padding([10, 20])
+                                .on_press(Message::ChangePage(
+                                    crate::ui::main_window::Page::Manifest,
+                                ))
+                                .into(),
+                            button(text("Leftover App Data"))
+                                .style(ButtonStyle::Secondary)
+                                .padding([10, 20])
+                                .on_press(Message::ChangePage(
+                                    crate::ui::main_window::Page::AppData,
+                                ))
+                                .into(),
+                            button(text("Runtimes & Extensions"))
+                                .style(ButtonStyle::Secondary)
+                                .padding([10, 20])
+                                .on_press(Message::ChangePage(
+                                    crate::ui::main_window::Page::Runtimes,
+                                ))
+                                .into(),
                             button(appearance::icon('\u{eb37}'))
                                 .on_press(Message::RequestRefreshInstalledApps)
                                 .padding(10.)
@@ -196,6 +449,7 @@ impl InstalledPage {
                     .spacing(10.0)
                     .into()
                 },
+                self.disk_usage_view(),
             ])
             .spacing(10.0),
         )
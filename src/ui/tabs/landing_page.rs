@@ -1,5 +1,6 @@
 use std::{
     cell::RefCell,
+    collections::HashMap,
     path::PathBuf,
     sync::{Arc, Mutex},
     time::Duration,
@@ -21,17 +22,31 @@ use once_cell::sync::Lazy;
 
 use super::Tab;
 use crate::{
-    backend::flatpak_backend::Package,
+    backend::flatpak_backend::{OperationProgress, Package, PackageId},
     db::search,
     ui::{
         appearance::{self, ButtonStyle, ContainerStyle, Theme},
-        custom_widgets::appcard::AppCard,
+        custom_widgets::{appcard::AppCard, operation_progress::operation_progress},
         main_window::{Config, Message},
     },
 };
 
 static CONTAINER: Lazy<id::Container> = Lazy::new(id::Container::unique);
 
+/// The main appstream categories as stored in the database, and how they are labelled.
+const CATEGORIES: [(&str, &str); 10] = [
+    ("AudioVideo", "Audio & Video"),
+    ("Development", "Developer Tools"),
+    ("Education", "Education"),
+    ("Game", "Games"),
+    ("Graphics", "Graphics"),
+    ("Network", "Networking"),
+    ("Office", "Productivity"),
+    ("Science", "Science"),
+    ("System", "System"),
+    ("Utility", "Utilities"),
+];
+
 fn anim_searchbox_open() -> Chain {
     chain![
         CONTAINER,
@@ -56,6 +71,8 @@ fn anim_searchbox_close() -> Chain {
 
 pub struct LandingPage {
     pub search_term: String,
+    /// The category whose apps are listed instead of search results
+    pub category: Option<String>,
     pub found_apps: Arc<Mutex<RefCell<Vec<Package>>>>,
     pub staff_pick_apps: Arc<Mutex<RefCell<Vec<Package>>>>,
     theme: Theme,
@@ -64,6 +81,7 @@ pub struct LandingPage {
     status: Status,
     db_loading_progress: u32,
     db_loaded: bool,
+    operations: Arc<Mutex<HashMap<PackageId, OperationProgress>>>,
 }
 
 enum Status {
@@ -74,6 +92,7 @@ enum Status {
 
 pub enum LandingPageMessage {
     Search(String),
+    Category(Option<String>),
     StopSearch,
     Found(Arc<Vec<Package>>),
     StaffPicks(Arc<Vec<Package>>),
@@ -83,14 +102,19 @@ pub enum LandingPageMessage {
 }
 
 impl LandingPage {
-    pub fn new(config: Config) -> Self {
+    pub fn new(
+        config: Config,
+        operations: Arc<Mutex<HashMap<PackageId, OperationProgress>>>,
+    ) -> Self {
         let timeline = Timeline::new();
         Self {
             search_term: Default::default(),
+            category: None,
             found_apps: Default::default(),
             staff_pick_apps: Default::default(),
             theme: Default::default(),
             config,
+            operations,
             timeline,
             status: Status::Default,
             db_loading_progress: 0,
@@ -104,12 +128,22 @@ impl LandingPage {
                 if st.len() < 3 {
                     self.found_apps.lock().unwrap().get_mut().clear();
                 }
+                self.category = None;
                 self.search_term = st;
                 Command::none()
             }
+            LandingPageMessage::Category(category) => {
+                // The apps of a new category replace the results once they are found
+                if category.is_none() {
+                    self.found_apps.lock().unwrap().get_mut().clear();
+                }
+                self.category = category;
+                Command::none()
+            }
             LandingPageMessage::StopSearch => {
                 self.status = Status::StoppingSearch;
                 self.search_term.clear();
+                self.category = None;
                 self.found_apps.lock().unwrap().get_mut().clear();
                 self.timeline
                     .set_chain(anim_searchbox_close())
@@ -163,6 +197,7 @@ impl LandingPage {
         .padding(10)
         .center_x()
     }
+
     fn app_card(
         &self,
         package: &Package,
@@ -183,18 +218,33 @@ impl LandingPage {
                         // .style(theme::Text::Color(self.style_sheet().app_card_text_color))
                         .size(18)
                         .into(),
+                    text(package.size_summary().unwrap_or_default())
+                        .size(14)
+                        .into(),
+                    operation_progress(&self.operations, &package.name),
                 ])
                 .width(Length::Shrink)
                 .into(),
                 if show_buttons {
                     if installed {
-                        button(appearance::icon('\u{f1767}'))
-                            .on_press(Message::Uninstall(package.name.clone()))
-                            .style(ButtonStyle::Icon)
-                            .into()
+                        column(vec![
+                            button(appearance::icon('\u{f03cc}'))
+                                .on_press(Message::Launch(package.name.clone()))
+                                .style(ButtonStyle::Icon)
+                                .into(),
+                            button(appearance::icon('\u{f1767}'))
+                                .on_press(Message::Uninstall((
+                                    package.name.clone(),
+                                    package.scope.clone(),
+                                )))
+                                .style(ButtonStyle::Icon)
+                                .into(),
+                        ])
+                        .spacing(10.0)
+                        .into()
                     } else {
                         button(appearance::icon('\u{f498}'))
-                            .on_press(Message::Uninstall(package.name.clone()))
+                            .on_press(Message::Install(package.name.clone()))
                             .style(ButtonStyle::Icon)
                             .into()
                     }
@@ -212,6 +262,27 @@ impl LandingPage {
         .into()
     }
 
+    fn categories(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let buttons = CATEGORIES
+            .iter()
+            .map(|(category, label)| {
+                button(text(*label))
+                    .style(if self.category.as_deref() == Some(*category) {
+                        ButtonStyle::Primary
+                    } else {
+                        ButtonStyle::Secondary
+                    })
+                    .padding([6, 14])
+                    .on_press(Message::Category(category.to_string()))
+                    .into()
+            })
+            .collect();
+        wrap::Wrap::with_elements(buttons)
+            .spacing(10.0)
+            .line_spacing(10.0)
+            .into()
+    }
+
     fn search_view(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
         let loading: iced::Element<_, _> = Column::new()
             .push(text(format!(
@@ -307,9 +378,25 @@ impl LandingPage {
                             .padding([10, 20])
                             .on_press(Message::ChangePage(crate::ui::main_window::Page::Installed))
                             .into(),
+                            button(
+                                row![appearance::icon('\u{f048b}'), text("Remotes"),].spacing(10.),
+                            )
+                            .style(ButtonStyle::Secondary)
+                            .padding([10, 20])
+                            .on_press(Message::ChangePage(crate::ui::main_window::Page::Remotes))
+                            .into(),
+                            button(
+                                row![appearance::icon('\u{f0214}'), text("Open file"),]
+                                    .spacing(10.),
+                            )
+                            .style(ButtonStyle::Secondary)
+                            .padding([10, 20])
+                            .on_press(Message::PickFile)
+                            .into(),
                         ])
                         .into(),
                         horizontal_rule(4.).into(),
+                        self.categories(),
                         scrollable(
                             container(
                                 wrap::Wrap::with_elements(apps)
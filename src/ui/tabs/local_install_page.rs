@@ -0,0 +1,177 @@
+use iced::{
+    widget::{button, column, container, horizontal_rule, horizontal_space, row, text},
+    Length,
+};
+use iced_aw::{graphics::icons::Icon, TabLabel};
+
+use crate::{
+    backend::{
+        flatpak_backend::InstallationScope,
+        local_packages::{LocalPackage, LocalPackageKind},
+    },
+    ui::{
+        appearance::{self, ButtonStyle, ContainerStyle, Theme},
+        main_window::{Config, Message},
+    },
+};
+
+use super::Tab;
+
+/// Asks for confirmation before installing a `.flatpakref` or `.flatpak` file.
+pub struct LocalInstallPage {
+    config: Config,
+    pub package: Option<LocalPackage>,
+}
+
+#[derive(Debug, Clone)]
+pub enum LocalInstallPageMessage {
+    Opened(LocalPackage),
+    ScopeSelected(InstallationScope),
+}
+
+impl LocalInstallPage {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            package: None,
+        }
+    }
+
+    pub fn update(&mut self, message: LocalInstallPageMessage) {
+        match message {
+            LocalInstallPageMessage::Opened(package) => self.package = Some(package),
+            LocalInstallPageMessage::ScopeSelected(scope) => {
+                if let Some(package) = self.package.as_mut() {
+                    package.scope = scope;
+                }
+            }
+        }
+    }
+
+    fn field<'a>(
+        &self,
+        label: &str,
+        value: &Option<String>,
+    ) -> iced::Element<'a, Message, iced::Renderer<Theme>> {
+        row(vec![
+            text(label).size(18).width(Length::Fixed(160.)).into(),
+            text(value.clone().unwrap_or("-".to_string()))
+                .size(18)
+                .into(),
+        ])
+        .spacing(10.0)
+        .into()
+    }
+
+    fn scope_button<'a>(
+        &self,
+        package: &LocalPackage,
+        scope: InstallationScope,
+    ) -> iced::Element<'a, Message, iced::Renderer<Theme>> {
+        button(text(scope.to_string()))
+            .style(if package.scope == scope {
+                ButtonStyle::Primary
+            } else {
+                ButtonStyle::Secondary
+            })
+            .padding([6, 14])
+            .on_press(Message::LocalInstallPage(
+                LocalInstallPageMessage::ScopeSelected(scope),
+            ))
+            .into()
+    }
+
+    fn confirmation_view(
+        &self,
+        package: &LocalPackage,
+    ) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let kind = match package.kind {
+            LocalPackageKind::Flatpakref => "Flatpak reference",
+            LocalPackageKind::Bundle => "Flatpak bundle",
+        };
+        column(vec![
+            text(package.title.clone().unwrap_or(package.name.clone()))
+                .size(30)
+                .into(),
+            text(format!("{} ({})", package.path.display(), kind))
+                .size(14)
+                .into(),
+            horizontal_rule(1.).into(),
+            self.field("Name", &Some(package.name.clone())),
+            self.field("Branch", &package.branch),
+            self.field("Remote", &package.remote),
+            self.field("Runtime", &package.runtime),
+            self.field("Runtime repository", &package.runtime_repo),
+            row(vec![
+                text("Install for")
+                    .size(18)
+                    .width(Length::Fixed(160.))
+                    .into(),
+                self.scope_button(package, InstallationScope::User),
+                self.scope_button(package, InstallationScope::System),
+            ])
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center)
+            .into(),
+            horizontal_rule(1.).into(),
+            row(vec![
+                horizontal_space(Length::Fill).into(),
+                button(text("Cancel"))
+                    .style(ButtonStyle::Secondary)
+                    .padding([10, 20])
+                    .on_press(Message::ChangePage(
+                        crate::ui::main_window::Page::LandingPage,
+                    ))
+                    .into(),
+                button(row![appearance::icon('\u{f01da}'), text("Install")].spacing(10.))
+                    .style(ButtonStyle::Primary)
+                    .padding([10, 20])
+                    .on_press(Message::InstallLocal)
+                    .into(),
+            ])
+            .spacing(10.0)
+            .into(),
+        ])
+        .spacing(10.0)
+        .into()
+    }
+}
+
+impl Tab for LocalInstallPage {
+    type Message = Message;
+
+    fn title(&self) -> String {
+        "Install file".into()
+    }
+
+    fn tab_label(&self) -> iced_aw::TabLabel {
+        TabLabel::IconText(Icon::Check.into(), self.title())
+    }
+
+    fn theme(&self) -> Theme {
+        if self.config.dark_mode {
+            Theme::Dark
+        } else {
+            Theme::Light
+        }
+    }
+
+    fn view(&self) -> iced::Element<Self::Message, iced::Renderer<Theme>> {
+        container(match &self.package {
+            Some(package) => self.confirmation_view(package),
+            None => column(vec![
+                text("No file opened").size(30).into(),
+                button(row![appearance::icon('\u{f0214}'), text("Open a file")].spacing(10.))
+                    .style(ButtonStyle::Primary)
+                    .padding([10, 20])
+                    .on_press(Message::PickFile)
+                    .into(),
+            ])
+            .spacing(10.0)
+            .into(),
+        })
+        .padding(10.0)
+        .style(ContainerStyle::Default)
+        .into()
+    }
+}
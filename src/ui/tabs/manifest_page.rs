@@ -0,0 +1,292 @@
+use std::sync::Arc;
+
+use iced::{
+    widget::{
+        button, column, container, horizontal_rule, horizontal_space, row, scrollable, text,
+        text_input,
+    },
+    Length,
+};
+use iced_aw::{graphics::icons::Icon, TabLabel};
+use libflatpak::glib;
+
+use crate::{
+    backend::manifest::{ImportPlan, ManifestApp},
+    ui::{
+        appearance::{self, ButtonStyle, ContainerStyle, Theme},
+        main_window::{Config, Message},
+    },
+};
+
+use super::Tab;
+
+/// Exports the installed apps to a manifest and imports one after previewing what it installs.
+pub struct ManifestPage {
+    config: Config,
+
+    pub export_path: String,
+    pub pin_commits: bool,
+    pub import_path: String,
+    /// The preview of the last manifest opened for import
+    pub plan: Option<Arc<ImportPlan>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ManifestPageMessage {
+    ExportPathChanged(String),
+    TogglePinCommits,
+    ImportPathChanged(String),
+    Planned(Arc<ImportPlan>),
+    ClearPlan,
+}
+
+impl ManifestPage {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            export_path: glib::home_dir()
+                .join("bazaar-apps.toml")
+                .display()
+                .to_string(),
+            pin_commits: false,
+            import_path: Default::default(),
+            plan: None,
+        }
+    }
+
+    pub fn update(&mut self, message: ManifestPageMessage) {
+        match message {
+            ManifestPageMessage::ExportPathChanged(path) => self.export_path = path,
+            ManifestPageMessage::TogglePinCommits => self.pin_commits = !self.pin_commits,
+            ManifestPageMessage::ImportPathChanged(path) => {
+                self.import_path = path;
+                self.plan = None;
+            }
+            ManifestPageMessage::Planned(plan) => {
+                self.import_path = plan.path.display().to_string();
+                self.plan = Some(plan);
+            }
+            ManifestPageMessage::ClearPlan => self.plan = None,
+        }
+    }
+
+    fn export_view(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        column(vec![
+            text("Export").size(24).into(),
+            text("Writes the installed apps to a .toml or .json manifest")
+                .size(14)
+                .into(),
+            row(vec![
+                text_input("Path of the manifest", &self.export_path)
+                    .on_input(|path| {
+                        Message::ManifestPage(ManifestPageMessage::ExportPathChanged(path))
+                    })
+                    .padding([4.0, 12.0, 4.0, 12.0])
+                    .width(Length::Fill)
+                    .into(),
+                button(text("Pin commits"))
+                    .style(if self.pin_commits {
+                        ButtonStyle::Primary
+                    } else {
+                        ButtonStyle::Secondary
+                    })
+                    .padding([6, 14])
+                    .on_press(Message::ManifestPage(ManifestPageMessage::TogglePinCommits))
+                    .into(),
+                button(row![appearance::icon('\u{f0207}'), text("Export")].spacing(10.))
+                    .style(ButtonStyle::Primary)
+                    .padding([6, 14])
+                    .on_press(Message::ExportManifest)
+                    .into(),
+            ])
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center)
+            .into(),
+        ])
+        .spacing(10.0)
+        .into()
+    }
+
+    fn app_row<'a>(&self, app: &ManifestApp) -> iced::Element<'a, Message, iced::Renderer<Theme>> {
+        let mut details = format!("{} from {}", app.installation, app.remote);
+        if let Some(commit) = &app.commit {
+            details = format!("{}, pinned to {}", details, &commit[..commit.len().min(12)]);
+        }
+        row(vec![
+            text(app.name()).size(16).width(Length::Fixed(300.)).into(),
+            text(&app.branch).size(14).width(Length::Fixed(100.)).into(),
+            text(details).size(14).into(),
+        ])
+        .spacing(10.0)
+        .into()
+    }
+
+    fn plan_view(&self, plan: &ImportPlan) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let mut items = vec![];
+        section(
+            &mut items,
+            format!(
+                "Transaction ({} to download)",
+                glib::format_size(plan.download_size())
+            ),
+            plan.operations
+                .iter()
+                .map(|op| {
+                    row(vec![
+                        text(op.kind.to_string())
+                            .size(14)
+                            .width(Length::Fixed(120.))
+                            .into(),
+                        text(&op.ref_).size(14).width(Length::Fill).into(),
+                        text(format!("{} ({})", op.remote, op.scope.to_string()))
+                            .size(14)
+                            .into(),
+                        text(glib::format_size(op.download_size)).size(14).into(),
+                    ])
+                    .spacing(10.0)
+                    .into()
+                })
+                .collect(),
+        );
+        section(
+            &mut items,
+            format!("To install ({})", plan.missing.len()),
+            plan.missing.iter().map(|app| self.app_row(app)).collect(),
+        );
+        section(
+            &mut items,
+            format!(
+                "Skipped, the installation or remote is not set up here ({})",
+                plan.unavailable.len()
+            ),
+            plan.unavailable
+                .iter()
+                .map(|app| self.app_row(app))
+                .collect(),
+        );
+        section(
+            &mut items,
+            format!("Already installed ({})", plan.already_installed.len()),
+            plan.already_installed
+                .iter()
+                .map(|app| self.app_row(app))
+                .collect(),
+        );
+        let mut install = button(
+            row![
+                appearance::icon('\u{f01da}'),
+                text(format!("Install {} apps", plan.missing.len()))
+            ]
+            .spacing(10.),
+        )
+        .style(ButtonStyle::Primary)
+        .padding([10, 20]);
+        if !plan.missing.is_empty() {
+            install = install.on_press(Message::ApplyImport);
+        }
+        column(vec![
+            scrollable(column(items).spacing(6.0))
+                .height(Length::Fill)
+                .into(),
+            row(vec![
+                horizontal_space(Length::Fill).into(),
+                button(text("Cancel"))
+                    .style(ButtonStyle::Secondary)
+                    .padding([10, 20])
+                    .on_press(Message::ManifestPage(ManifestPageMessage::ClearPlan))
+                    .into(),
+                install.into(),
+            ])
+            .spacing(10.0)
+            .into(),
+        ])
+        .spacing(10.0)
+        .into()
+    }
+
+    fn import_view(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        column(vec![
+            text("Import").size(24).into(),
+            text("Installs the apps of a manifest that are missing here")
+                .size(14)
+                .into(),
+            row(vec![
+                text_input("Path of the manifest", &self.import_path)
+                    .on_input(|path| {
+                        Message::ManifestPage(ManifestPageMessage::ImportPathChanged(path))
+                    })
+                    .on_submit(Message::PreviewImport)
+                    .padding([4.0, 12.0, 4.0, 12.0])
+                    .width(Length::Fill)
+                    .into(),
+                button(text("Preview"))
+                    .style(ButtonStyle::Secondary)
+                    .padding([6, 14])
+                    .on_press(Message::PreviewImport)
+                    .into(),
+            ])
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center)
+            .into(),
+            match &self.plan {
+                Some(plan) => self.plan_view(plan),
+                None => column(vec![]).into(),
+            },
+        ])
+        .spacing(10.0)
+        .into()
+    }
+}
+
+/// Appends `rows` below a heading, nothing if there are no rows.
+fn section<'a>(
+    items: &mut Vec<iced::Element<'a, Message, iced::Renderer<Theme>>>,
+    title: String,
+    rows: Vec<iced::Element<'a, Message, iced::Renderer<Theme>>>,
+) {
+    if !rows.is_empty() {
+        items.push(text(title).size(20).into());
+        items.extend(rows);
+    }
+}
+
+impl Tab for ManifestPage {
+    type Message = Message;
+
+    fn title(&self) -> String {
+        "Import & Export".into()
+    }
+
+    fn tab_label(&self) -> iced_aw::TabLabel {
+        TabLabel::IconText(Icon::Check.into(), self.title())
+    }
+
+    fn theme(&self) -> Theme {
+        if self.config.dark_mode {
+            Theme::Dark
+        } else {
+            Theme::Light
+        }
+    }
+
+    fn view(&self) -> iced::Element<Self::Message, iced::Renderer<Theme>> {
+        container(
+            column(vec![
+                button(appearance::icon('\u{f030d}'))
+                    .on_press(Message::ChangePage(crate::ui::main_window::Page::Installed))
+                    .padding(10.)
+                    .style(ButtonStyle::Icon)
+                    .into(),
+                text("Import & Export").size(30).into(),
+                horizontal_rule(1.).into(),
+                self.export_view(),
+                horizontal_rule(1.).into(),
+                self.import_view(),
+            ])
+            .spacing(10.0),
+        )
+        .padding(10.0)
+        .style(ContainerStyle::Default)
+        .into()
+    }
+}
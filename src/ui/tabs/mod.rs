@@ -3,9 +3,15 @@ use iced_aw::TabLabel;
 
 use super::appearance::Theme;
 
+pub mod app_data_page;
 pub mod app_view;
 pub mod installed_page;
 pub mod landing_page;
+pub mod local_install_page;
+pub mod manifest_page;
+pub mod remotes_page;
+pub mod runtimes_page;
+pub mod sync_page;
 
 pub trait Tab {
     type Message;
@@ -0,0 +1,332 @@
+use std::{path::PathBuf, sync::Arc};
+
+use iced::{
+    widget::{
+        button, column, container, horizontal_rule, horizontal_space, row, scrollable, text,
+        text_input,
+    },
+    Length,
+};
+use iced_aw::{graphics::icons::Icon, TabLabel};
+
+use crate::{
+    backend::{
+        flatpak_backend::InstallationScope,
+        remotes::{GpgVerification, RemoteChange, RemoteInfo},
+    },
+    ui::{
+        appearance::{self, ButtonStyle, ContainerStyle, Theme},
+        main_window::{Config, Message},
+    },
+};
+
+use super::Tab;
+
+pub struct RemotesPage {
+    config: Config,
+
+    remotes: Arc<Vec<RemoteInfo>>,
+    pub name: String,
+    pub location: String,
+    pub scope: InstallationScope,
+    /// The key file a bare repository URL is verified with
+    pub gpg_key: String,
+    pub skip_gpg_verify: bool,
+    /// Whether the warning about skipping the verification is showing
+    confirming_skip: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum RemotesPageMessage {
+    Listed(Arc<Vec<RemoteInfo>>),
+    NameChanged(String),
+    LocationChanged(String),
+    ScopeSelected(InstallationScope),
+    GpgKeyChanged(String),
+    ToggleSkipGpgVerify,
+    ConfirmSkipGpgVerify,
+    CancelSkipGpgVerify,
+    ClearForm,
+}
+
+impl RemotesPage {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            remotes: Default::default(),
+            name: Default::default(),
+            location: Default::default(),
+            scope: Default::default(),
+            gpg_key: Default::default(),
+            skip_gpg_verify: false,
+            confirming_skip: false,
+        }
+    }
+
+    pub fn update(&mut self, message: RemotesPageMessage) {
+        match message {
+            RemotesPageMessage::Listed(remotes) => {
+                println!("Refreshed remotes");
+                self.remotes = remotes;
+            }
+            RemotesPageMessage::NameChanged(name) => self.name = name,
+            RemotesPageMessage::LocationChanged(location) => self.location = location,
+            RemotesPageMessage::ScopeSelected(scope) => self.scope = scope,
+            RemotesPageMessage::GpgKeyChanged(path) => self.gpg_key = path,
+            RemotesPageMessage::ToggleSkipGpgVerify => {
+                if self.skip_gpg_verify {
+                    self.skip_gpg_verify = false;
+                } else {
+                    self.confirming_skip = true;
+                }
+            }
+            RemotesPageMessage::ConfirmSkipGpgVerify => {
+                self.skip_gpg_verify = true;
+                self.confirming_skip = false;
+                self.gpg_key.clear();
+            }
+            RemotesPageMessage::CancelSkipGpgVerify => self.confirming_skip = false,
+            RemotesPageMessage::ClearForm => {
+                self.name.clear();
+                self.location.clear();
+                self.gpg_key.clear();
+                self.skip_gpg_verify = false;
+                self.confirming_skip = false;
+            }
+        }
+    }
+
+    /// Whether the location is a bare repository URL rather than a `.flatpakrepo` file, which
+    /// comes with its own key.
+    fn needs_gpg(&self) -> bool {
+        !self.location.is_empty() && !self.location.ends_with(".flatpakrepo")
+    }
+
+    pub fn gpg_verification(&self) -> Option<GpgVerification> {
+        if !self.needs_gpg() {
+            None
+        } else if self.skip_gpg_verify {
+            Some(GpgVerification::Disabled)
+        } else if !self.gpg_key.is_empty() {
+            Some(GpgVerification::Key(PathBuf::from(&self.gpg_key)))
+        } else {
+            None
+        }
+    }
+
+    fn toggle<'a>(
+        &self,
+        label: &str,
+        active: bool,
+        message: Message,
+    ) -> iced::Element<'a, Message, iced::Renderer<Theme>> {
+        button(text(label))
+            .style(if active {
+                ButtonStyle::Primary
+            } else {
+                ButtonStyle::Secondary
+            })
+            .padding([6, 14])
+            .on_press(message)
+            .into()
+    }
+
+    fn remote_row(&self, remote: &RemoteInfo) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let modify = |change| Message::ModifyRemote((remote.clone(), change));
+        row(vec![
+            column(vec![
+                text(remote.title.clone().unwrap_or(remote.name.clone()))
+                    .size(24)
+                    .into(),
+                text(format!(
+                    "{} ({}) {}",
+                    remote.name,
+                    remote.scope.to_string(),
+                    remote.url.clone().unwrap_or_default()
+                ))
+                .size(14)
+                .into(),
+            ])
+            .width(Length::Fill)
+            .into(),
+            self.toggle(
+                "Enabled",
+                !remote.disabled,
+                modify(RemoteChange::SetDisabled(!remote.disabled)),
+            ),
+            self.toggle(
+                "Browsable",
+                !remote.noenumerate,
+                modify(RemoteChange::SetNoEnumerate(!remote.noenumerate)),
+            ),
+            button(appearance::icon('\u{f0143}'))
+                .on_press(modify(RemoteChange::SetPriority(remote.priority + 1)))
+                .style(ButtonStyle::Icon)
+                .into(),
+            text(format!("Priority {}", remote.priority))
+                .size(14)
+                .into(),
+            button(appearance::icon('\u{f0140}'))
+                .on_press(modify(RemoteChange::SetPriority(remote.priority - 1)))
+                .style(ButtonStyle::Icon)
+                .into(),
+            button(appearance::icon('\u{f1767}'))
+                .on_press(modify(RemoteChange::Remove))
+                .style(ButtonStyle::Icon)
+                .into(),
+        ])
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center)
+        .into()
+    }
+
+    fn add_remote_form(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let mut add = button(row![appearance::icon('\u{f0415}'), text("Add")].spacing(10.))
+            .style(ButtonStyle::Primary)
+            .padding([10, 20]);
+        if !self.name.is_empty()
+            && !self.location.is_empty()
+            && (!self.needs_gpg() || self.gpg_verification().is_some())
+        {
+            add = add.on_press(Message::AddRemote);
+        }
+        let form = row(vec![
+            text_input("Name", &self.name)
+                .on_input(|name| Message::RemotesPage(RemotesPageMessage::NameChanged(name)))
+                .padding([4.0, 12.0, 4.0, 12.0])
+                .width(Length::Fixed(150.))
+                .into(),
+            text_input("URL or .flatpakrepo file", &self.location)
+                .on_input(|location| {
+                    Message::RemotesPage(RemotesPageMessage::LocationChanged(location))
+                })
+                .padding([4.0, 12.0, 4.0, 12.0])
+                .width(Length::Fill)
+                .into(),
+            self.toggle(
+                "User",
+                self.scope == InstallationScope::User,
+                Message::RemotesPage(RemotesPageMessage::ScopeSelected(InstallationScope::User)),
+            ),
+            self.toggle(
+                "System",
+                self.scope == InstallationScope::System,
+                Message::RemotesPage(RemotesPageMessage::ScopeSelected(InstallationScope::System)),
+            ),
+            add.into(),
+        ])
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center);
+        if !self.needs_gpg() {
+            return form.into();
+        }
+        column(vec![form.into(), self.gpg_form()])
+            .spacing(10.0)
+            .into()
+    }
+
+    /// Picks the key a bare repository URL is verified with, or turns the verification off
+    /// once the user confirmed they understand what that means.
+    fn gpg_form(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        if self.confirming_skip {
+            return row(vec![
+                text("Without verification anyone who can tamper with the connection to this repository can install code on this machine")
+                    .size(14)
+                    .width(Length::Fill)
+                    .into(),
+                button(text("Cancel"))
+                    .style(ButtonStyle::Secondary)
+                    .padding([6, 14])
+                    .on_press(Message::RemotesPage(RemotesPageMessage::CancelSkipGpgVerify))
+                    .into(),
+                button(text("Skip verification"))
+                    .style(ButtonStyle::Primary)
+                    .padding([6, 14])
+                    .on_press(Message::RemotesPage(RemotesPageMessage::ConfirmSkipGpgVerify))
+                    .into(),
+            ])
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center)
+            .into();
+        }
+        let mut key = text_input("Path of the GPG key of the repository", &self.gpg_key)
+            .padding([4.0, 12.0, 4.0, 12.0])
+            .width(Length::Fill);
+        if !self.skip_gpg_verify {
+            key =
+                key.on_input(|path| Message::RemotesPage(RemotesPageMessage::GpgKeyChanged(path)));
+        }
+        row(vec![
+            key.into(),
+            self.toggle(
+                "Skip verification",
+                self.skip_gpg_verify,
+                Message::RemotesPage(RemotesPageMessage::ToggleSkipGpgVerify),
+            ),
+        ])
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center)
+        .into()
+    }
+
+    fn remotes_view(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let remotes = self
+            .remotes
+            .iter()
+            .map(|remote| self.remote_row(remote))
+            .collect();
+        container(
+            column(vec![
+                button(appearance::icon('\u{f030d}'))
+                    .on_press(Message::ChangePage(
+                        crate::ui::main_window::Page::LandingPage,
+                    ))
+                    .padding(10.)
+                    .style(ButtonStyle::Icon)
+                    .into(),
+                row(vec![
+                    text("Remotes").size(30).into(),
+                    horizontal_space(Length::Fill).into(),
+                    button(appearance::icon('\u{eb37}'))
+                        .on_press(Message::RequestRefreshRemotes)
+                        .padding(10.)
+                        .style(ButtonStyle::Icon)
+                        .into(),
+                ])
+                .into(),
+                horizontal_rule(1.).into(),
+                self.add_remote_form(),
+                horizontal_rule(1.).into(),
+                scrollable(column(remotes).spacing(10.0)).into(),
+            ])
+            .spacing(10.0),
+        )
+        .padding(10.0)
+        .style(ContainerStyle::Default)
+        .into()
+    }
+}
+
+impl Tab for RemotesPage {
+    type Message = Message;
+
+    fn title(&self) -> String {
+        "Remotes".into()
+    }
+
+    fn tab_label(&self) -> iced_aw::TabLabel {
+        TabLabel::IconText(Icon::Check.into(), self.title())
+    }
+
+    fn theme(&self) -> Theme {
+        if self.config.dark_mode {
+            Theme::Dark
+        } else {
+            Theme::Light
+        }
+    }
+
+    fn view(&self) -> iced::Element<Self::Message, iced::Renderer<Theme>> {
+        self.remotes_view()
+    }
+}
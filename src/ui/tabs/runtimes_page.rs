@@ -0,0 +1,162 @@
+use std::sync::Arc;
+
+use iced::{
+    widget::{button, column, container, horizontal_rule, horizontal_space, row, scrollable, text},
+    Length,
+};
+use iced_aw::{graphics::icons::Icon, TabLabel};
+use libflatpak::glib;
+
+use crate::{
+    backend::runtimes::RuntimeInfo,
+    ui::{
+        appearance::{self, ButtonStyle, ContainerStyle, Theme},
+        main_window::{Config, Message},
+    },
+};
+
+use super::Tab;
+
+pub struct RuntimesPage {
+    config: Config,
+
+    runtimes: Option<Arc<Vec<RuntimeInfo>>>,
+}
+
+pub enum RuntimesPageMessage {
+    Listed(Arc<Vec<RuntimeInfo>>),
+}
+
+impl RuntimesPage {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            runtimes: None,
+        }
+    }
+
+    pub fn update(&mut self, message: RuntimesPageMessage) {
+        match message {
+            RuntimesPageMessage::Listed(runtimes) => {
+                println!("Refreshed runtimes");
+                self.runtimes = Some(runtimes);
+            }
+        }
+    }
+
+    fn runtime_row(&self, runtime: &RuntimeInfo) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let used_by = if runtime.used_by.is_empty() {
+            "Not used by any app".to_string()
+        } else {
+            format!("Used by {}", runtime.used_by.join(", "))
+        };
+        row(vec![
+            column(vec![
+                text(&runtime.name).size(22).into(),
+                text(format!(
+                    "{} {} ({}) · {}",
+                    runtime.kind.to_string(),
+                    runtime.branch.clone().unwrap_or_default(),
+                    runtime.scope.to_string(),
+                    glib::format_size(runtime.installed_size)
+                ))
+                .size(14)
+                .into(),
+                text(used_by).size(14).into(),
+            ])
+            .width(Length::Fill)
+            .into(),
+            if runtime.unused {
+                text("Unused").size(16).into()
+            } else {
+                horizontal_space(Length::Shrink).into()
+            },
+        ])
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center)
+        .into()
+    }
+
+    fn runtimes_view(&self) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let list: iced::Element<Message, iced::Renderer<Theme>> = match &self.runtimes {
+            Some(runtimes) => scrollable(
+                column(
+                    runtimes
+                        .iter()
+                        .map(|runtime| self.runtime_row(runtime))
+                        .collect(),
+                )
+                .spacing(10.0),
+            )
+            .into(),
+            None => text("Loading").size(30).into(),
+        };
+        let unused: Vec<String> = self
+            .runtimes
+            .iter()
+            .flat_map(|runtimes| runtimes.iter().filter(|r| r.unused).map(|r| r.name.clone()))
+            .collect();
+        let mut remove_unused = button(
+            row![
+                appearance::icon('\u{f1767}'),
+                text(format!("Remove unused ({})", unused.len())),
+            ]
+            .spacing(10.),
+        )
+        .style(ButtonStyle::Primary)
+        .padding([10, 20]);
+        if !unused.is_empty() {
+            remove_unused = remove_unused.on_press(Message::RemoveUnused(unused));
+        }
+        container(
+            column(vec![
+                button(appearance::icon('\u{f030d}'))
+                    .on_press(Message::ChangePage(crate::ui::main_window::Page::Installed))
+                    .padding(10.)
+                    .style(ButtonStyle::Icon)
+                    .into(),
+                row(vec![
+                    text("Runtimes & Extensions").size(30).into(),
+                    horizontal_space(Length::Fill).into(),
+                    remove_unused.into(),
+                    button(appearance::icon('\u{eb37}'))
+                        .on_press(Message::RequestRefreshRuntimes)
+                        .padding(10.)
+                        .style(ButtonStyle::Icon)
+                        .into(),
+                ])
+                .into(),
+                horizontal_rule(1.).into(),
+                list,
+            ])
+            .spacing(10.0),
+        )
+        .padding(10.0)
+        .style(ContainerStyle::Default)
+        .into()
+    }
+}
+
+impl Tab for RuntimesPage {
+    type Message = Message;
+
+    fn title(&self) -> String {
+        "Runtimes & Extensions".into()
+    }
+
+    fn tab_label(&self) -> iced_aw::TabLabel {
+        TabLabel::IconText(Icon::Check.into(), self.title())
+    }
+
+    fn theme(&self) -> Theme {
+        if self.config.dark_mode {
+            Theme::Dark
+        } else {
+            Theme::Light
+        }
+    }
+
+    fn view(&self) -> iced::Element<Self::Message, iced::Renderer<Theme>> {
+        self.runtimes_view()
+    }
+}
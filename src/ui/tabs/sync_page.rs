@@ -0,0 +1,194 @@
+use std::sync::Arc;
+
+use iced::{
+    widget::{
+        button, column, container, horizontal_rule, horizontal_space, row, scrollable, text,
+        text_input,
+    },
+    Length,
+};
+use iced_aw::{graphics::icons::Icon, TabLabel};
+
+use crate::{
+    backend::sync::{SyncPlan, SyncStep},
+    ui::{
+        appearance::{self, ButtonStyle, ContainerStyle, Theme},
+        main_window::{Config, Message},
+    },
+};
+
+use super::Tab;
+
+/// Converges the machine on a state file of remotes and apps after showing the plan.
+pub struct SyncPage {
+    config: Config,
+
+    pub state_path: String,
+    pub plan: Option<Arc<SyncPlan>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum SyncPageMessage {
+    PathChanged(String),
+    Planned(Arc<SyncPlan>),
+    ClearPlan,
+}
+
+impl SyncPage {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            state_path: Default::default(),
+            plan: None,
+        }
+    }
+
+    pub fn update(&mut self, message: SyncPageMessage) {
+        match message {
+            SyncPageMessage::PathChanged(path) => {
+                self.state_path = path;
+                self.plan = None;
+            }
+            SyncPageMessage::Planned(plan) => {
+                self.state_path = plan.path.display().to_string();
+                self.plan = Some(plan);
+            }
+            SyncPageMessage::ClearPlan => self.plan = None,
+        }
+    }
+
+    fn step_row<'a>(&self, step: &SyncStep) -> iced::Element<'a, Message, iced::Renderer<Theme>> {
+        let icon = match step {
+            SyncStep::AddRemote(_) => '\u{f0415}',
+            SyncStep::Install(_) => '\u{f01da}',
+            SyncStep::Update(_) => '\u{f06b0}',
+            SyncStep::Remove(_) => '\u{f1767}',
+        };
+        row(vec![
+            appearance::icon(icon).into(),
+            text(step.to_string()).size(16).width(Length::Fill).into(),
+            text(step.scope().to_string()).size(14).into(),
+        ])
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center)
+        .into()
+    }
+
+    fn plan_view(&self, plan: &SyncPlan) -> iced::Element<Message, iced::Renderer<Theme>> {
+        let mut notes: Vec<iced::Element<Message, iced::Renderer<Theme>>> = vec![];
+        if !plan.unavailable.is_empty() {
+            let scopes: Vec<String> = plan.unavailable.iter().map(|s| s.to_string()).collect();
+            notes.push(
+                text(format!(
+                    "Skipped, these installations do not exist here: {}",
+                    scopes.join(", ")
+                ))
+                .size(14)
+                .into(),
+            );
+        }
+        if plan.is_empty() {
+            notes.insert(0, text("Everything matches the state file").size(20).into());
+            return column(notes).spacing(10.0).into();
+        }
+        if plan.removals() > 0 {
+            notes.push(
+                text(format!(
+                    "Applying uninstalls {} apps the state file does not list",
+                    plan.removals()
+                ))
+                .size(16)
+                .into(),
+            );
+        }
+        column(vec![
+            text(format!("Plan ({} steps)", plan.steps.len()))
+                .size(20)
+                .into(),
+            column(notes).spacing(6.0).into(),
+            scrollable(
+                column(plan.steps.iter().map(|step| self.step_row(step)).collect()).spacing(6.0),
+            )
+            .height(Length::Fill)
+            .into(),
+            row(vec![
+                horizontal_space(Length::Fill).into(),
+                button(text("Cancel"))
+                    .style(ButtonStyle::Secondary)
+                    .padding([10, 20])
+                    .on_press(Message::SyncPage(SyncPageMessage::ClearPlan))
+                    .into(),
+                button(row![appearance::icon('\u{f04e6}'), text("Apply")].spacing(10.))
+                    .style(ButtonStyle::Primary)
+                    .padding([10, 20])
+                    .on_press(Message::ApplySync)
+                    .into(),
+            ])
+            .spacing(10.0)
+            .into(),
+        ])
+        .spacing(10.0)
+        .into()
+    }
+}
+
+impl Tab for SyncPage {
+    type Message = Message;
+
+    fn title(&self) -> String {
+        "Sync".into()
+    }
+
+    fn tab_label(&self) -> iced_aw::TabLabel {
+        TabLabel::IconText(Icon::Check.into(), self.title())
+    }
+
+    fn theme(&self) -> Theme {
+        if self.config.dark_mode {
+            Theme::Dark
+        } else {
+            Theme::Light
+        }
+    }
+
+    fn view(&self) -> iced::Element<Self::Message, iced::Renderer<Theme>> {
+        container(
+            column(vec![
+                button(appearance::icon('\u{f030d}'))
+                    .on_press(Message::ChangePage(crate::ui::main_window::Page::Installed))
+                    .padding(10.)
+                    .style(ButtonStyle::Icon)
+                    .into(),
+                text("Sync to a State File").size(30).into(),
+                text("Adds the listed remotes, installs and updates the listed apps and, if the file asks for it, removes the others")
+                    .size(14)
+                    .into(),
+                horizontal_rule(1.).into(),
+                row(vec![
+                    text_input("Path of the .toml or .json state file", &self.state_path)
+                        .on_input(|path| Message::SyncPage(SyncPageMessage::PathChanged(path)))
+                        .on_submit(Message::PlanSync)
+                        .padding([4.0, 12.0, 4.0, 12.0])
+                        .width(Length::Fill)
+                        .into(),
+                    button(text("Plan"))
+                        .style(ButtonStyle::Secondary)
+                        .padding([6, 14])
+                        .on_press(Message::PlanSync)
+                        .into(),
+                ])
+                .spacing(10.0)
+                .align_items(iced::Alignment::Center)
+                .into(),
+                match &self.plan {
+                    Some(plan) => self.plan_view(plan),
+                    None => column(vec![]).into(),
+                },
+            ])
+            .spacing(10.0),
+        )
+        .padding(10.0)
+        .style(ContainerStyle::Default)
+        .into()
+    }
+}